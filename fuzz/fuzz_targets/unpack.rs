@@ -0,0 +1,11 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pinocchio_sample::instruction::RecordInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    // any input that decodes must be the canonical encoding of what it decodes to
+    if let Ok(instruction) = RecordInstruction::unpack(data) {
+        assert_eq!(instruction.pack(), data);
+    }
+});
@@ -7,6 +7,42 @@ pub enum RecordError {
 
     /// Calculation overflow
     Overflow,
+
+    /// Record account is not owned by the program
+    IncorrectOwner,
+
+    /// Record account is not writable
+    AccountNotWritable,
+
+    /// No authority transfer has been proposed for the record
+    NoPendingAuthority,
+
+    /// Record uses an older header version that does not support the instruction
+    OutdatedVersion,
+
+    /// Record is frozen and can no longer be modified
+    Frozen,
+
+    /// Invalid number of provided multisig signers
+    InvalidNumberOfProvidedSigners,
+
+    /// Invalid number of required multisig signers
+    InvalidNumberOfRequiredSigners,
+
+    /// Write falls outside the payload range permitted to the delegate
+    DelegateOutOfRange,
+
+    /// Delegate permission has expired
+    DelegateExpired,
+
+    /// Record payload has no room left for the appended data
+    RecordFull,
+
+    /// Record was written since the sequence number expected by a conditional write
+    WriteConflict,
+
+    /// Hash of the payload range does not match the expected hash
+    HashMismatch,
 }
 impl From<RecordError> for pinocchio::program_error::ProgramError {
     fn from(e: RecordError) -> Self {
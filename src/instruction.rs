@@ -1,60 +1,508 @@
-use pinocchio::program_error::ProgramError;
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use pinocchio::{account_info::MAX_PERMITTED_DATA_INCREASE, program_error::ProgramError};
 use std::mem::size_of;
 
+/// Hash function used by [`RecordInstruction::VerifyHash`]
+#[derive(Clone, Copy, Debug, Eq, FromPrimitive, PartialEq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Keccak256,
+    /// Only with the `blake3` feature, for clusters where the `sol_blake3` syscall is enabled
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum RecordInstruction<'a> {
     Initialize,
-    Write { offset: u64, data: &'a [u8] },
+    /// Writes `data` at `offset` of the payload.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority, or a delegate permitted to write the range
+    /// 2. `[]` The delegate entry, when written by a delegate
+    Write {
+        offset: u64,
+        data: &'a [u8],
+    },
     SetAuthority,
+    /// Closes the record, moving its lamports to the destination, wiping its data and
+    /// assigning it back to the system program.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable]` Receiver of the record lamports
     CloseAccount,
-    Reallocate { data_length: u64 },
+    /// Resizes the record so that it holds exactly `data_length` bytes after the header.
+    ///
+    /// Growth is capped at [`MAX_PERMITTED_DATA_INCREASE`] bytes per instruction, so
+    /// larger targets are reached by repeating the instruction, see [`reallocate_steps`].
+    /// The payload length reached is returned as a little-endian `u64` return data.
+    /// Shrinking counts as a write in [`crate::state::RecordData::write_count`].
+    ///
+    /// The record must already hold the rent of its grown size, see
+    /// [`RecordInstruction::ReallocateWithPayer`] to have it paid.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable]` Receiver of the excess rent-exempt lamports, only when shrinking
+    Reallocate {
+        data_length: u64,
+    },
+    /// Same as [`RecordInstruction::Initialize`], but requires the authority signature
+    /// and a rent-exempt record account.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    InitializeChecked,
+    /// Same as [`RecordInstruction::Initialize`], and writes `data` at the start of the
+    /// payload.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[]` The record authority
+    InitializeWithData {
+        data: &'a [u8],
+    },
+    /// Creates and initializes a rent-exempt record holding `data_length` bytes of payload.
+    ///
+    /// With an empty `seed` the record account signs its own creation, otherwise the
+    /// record lives at the address derived from the authority and `seed` with
+    /// `create_with_seed`. The created size is limited by [`MAX_PERMITTED_DATA_INCREASE`],
+    /// larger records are grown afterwards with [`RecordInstruction::Reallocate`].
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account, also `[signer]` when `seed` is empty
+    /// 1. `[]` The record authority, also `[signer]` when `seed` is not empty
+    /// 2. `[writable, signer]` The payer of the record rent
+    /// 3. `[]` The system program
+    CreateRecord {
+        data_length: u64,
+        seed: &'a [u8],
+    },
+    /// Creates and initializes a rent-exempt record at the program derived address
+    /// returned by [`crate::find_record_address`] for the authority and `seed`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable, signer]` The payer of the record rent
+    /// 3. `[]` The system program
+    CreateRecordPda {
+        data_length: u64,
+        seed: &'a [u8],
+    },
+    /// Proposes a new authority, which takes over once it signs
+    /// [`RecordInstruction::AcceptAuthority`].
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[]` The proposed authority
+    ProposeAuthority,
+    /// Makes the proposed authority the record authority.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The proposed authority
+    AcceptAuthority,
+    /// Withdraws the pending authority proposal.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    CancelProposal,
+    /// Permanently freezes the record, after which its data, size and authority can no
+    /// longer change and it cannot be closed.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    Freeze,
+    /// Upgrades a record stored with an older header version to the current one, growing
+    /// the account and moving the payload behind the larger header. Records already on
+    /// the current version are left untouched.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable, signer]` The payer of the missing rent, only with `with_payer`
+    /// 3. `[]` The system program, only with `with_payer`
+    Migrate {
+        with_payer: bool,
+    },
+    /// Initializes a multisig account, which can then be used as the authority of records.
+    /// Instructions checking the authority accept a multisig authority when at least `m`
+    /// of its signers are passed as signers after the accounts of the instruction.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The rent-exempt multisig account of [`crate::state::Multisig::LEN`] bytes
+    /// 1. ..`1+N` `[]` The N distinct signer accounts, between
+    ///    [`crate::state::Multisig::MIN_SIGNERS`] and [`crate::state::Multisig::MAX_SIGNERS`]
+    InitializeMultisig {
+        m: u8,
+    },
+    /// Lets the delegate write within `[start, end)` of the payload with
+    /// [`RecordInstruction::Write`], until `expiry_slot` included, or forever when it is
+    /// `0`. Approving an existing delegate replaces its permission.
+    ///
+    /// The permission lapses once the record authority changes, or once the record is
+    /// closed and created again in a later slot. Records still on the v1 header are
+    /// rejected.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable]` The delegate entry, at the address returned by
+    ///    [`crate::find_delegate_address`]
+    /// 3. `[]` The delegate
+    /// 4. `[writable, signer]` The payer of the delegate entry rent
+    /// 5. `[]` The system program
+    ApproveDelegate {
+        start: u64,
+        end: u64,
+        expiry_slot: u64,
+    },
+    /// Withdraws the permission of a delegate, closing its entry. Once the record is
+    /// closed, the authority that approved the permission signs in its place.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[]` The record account
+    /// 1. `[signer]` The record authority, or the approving authority of a closed record
+    /// 2. `[writable]` The delegate entry
+    /// 3. `[writable]` Receiver of the delegate entry lamports
+    RevokeDelegate,
+    /// Writes `data` right after the last written payload byte, as tracked by
+    /// [`crate::state::RecordData::data_len`], so concurrent writers need no offset. The
+    /// offset written at is returned as a little-endian `u64` return data.
+    ///
+    /// Without `with_payer` the instruction fails with
+    /// [`crate::error::RecordError::RecordFull`] when the payload is too small, with it the
+    /// record grows to fit `data`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable, signer]` The payer of the rent of the grown record, only with
+    ///    `with_payer`
+    /// 3. `[]` The system program, only with `with_payer`
+    Append {
+        data: &'a [u8],
+        with_payer: bool,
+    },
+    /// Same as [`RecordInstruction::Write`], but only applies while the record
+    /// [`crate::state::RecordData::write_count`] equals `expected_seq`, failing with
+    /// [`crate::error::RecordError::WriteConflict`] once another write got in first.
+    /// Records still on the v1 header have no write count and are rejected.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority, or a delegate permitted to write the range
+    /// 2. `[]` The delegate entry, when written by a delegate
+    WriteIfVersion {
+        expected_seq: u64,
+        offset: u64,
+        data: &'a [u8],
+    },
+    /// Writes each `(offset, data)` segment of the payload, checking all of them before
+    /// applying any. Segments are encoded compactly after a `u8` count, each as a `u32`
+    /// offset followed by `u16` length prefixed data, and there is at least one. Non-empty
+    /// segments may not overlap.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority, or a delegate permitted to write every segment
+    /// 2. `[]` The delegate entry, when written by a delegate
+    WriteMany {
+        segments: Vec<(u32, &'a [u8])>,
+    },
+    /// Sets `length` bytes of the payload starting at `offset` to `byte`.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority, or a delegate permitted to write the range
+    /// 2. `[]` The delegate entry, when written by a delegate
+    Fill {
+        offset: u64,
+        length: u64,
+        byte: u8,
+    },
+    /// Copies `length` payload bytes at `src_offset` of the source record to `dst_offset`
+    /// of the destination record. Both may be the same record, in which case the ranges
+    /// may overlap. Record data is public, so the source authority does not sign.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[]` The source record account, stored with any known header version
+    /// 1. `[writable]` The destination record account
+    /// 2. `[signer]` The destination record authority
+    CopyRange {
+        src_offset: u64,
+        dst_offset: u64,
+        length: u64,
+    },
+    /// Moves `length` payload bytes at `offset` by `delta` bytes, towards the end when
+    /// positive, to open or close a gap without rewriting the tail. Bytes left behind by
+    /// the move keep their value. When the moved bytes held the end of the written data,
+    /// [`crate::state::RecordData::data_len`] follows them.
+    ///
+    /// With `with_payer` the record grows when the moved bytes would end past the payload.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable, signer]` The payer of the rent of the grown record, only with
+    ///    `with_payer`
+    /// 3. `[]` The system program, only with `with_payer`
+    Shift {
+        offset: u64,
+        length: u64,
+        delta: i64,
+        with_payer: bool,
+    },
+    /// Fails with [`crate::error::RecordError::HashMismatch`] unless the `algorithm` hash
+    /// of `length` payload bytes at `offset` equals `expected`, letting an upload be
+    /// finalized in the same transaction as its check.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[]` The record account
+    VerifyHash {
+        offset: u64,
+        length: u64,
+        algorithm: HashAlgorithm,
+        expected: [u8; HASH_BYTES],
+    },
+    /// Returns `length` payload bytes at `offset` as return data, letting other programs
+    /// read a record without knowing its header layout. At most
+    /// [`pinocchio::program::MAX_RETURN_DATA`] bytes are returned.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[]` The record account
+    Read {
+        offset: u64,
+        length: u64,
+    },
+    /// Same as [`RecordInstruction::Reallocate`], but tops the grown record up to its
+    /// rent-exempt minimum from the payer.
+    ///
+    /// Accounts expected by this instruction:
+    ///
+    /// 0. `[writable]` The record account
+    /// 1. `[signer]` The record authority
+    /// 2. `[writable, signer]` The payer of the missing rent, and receiver of the excess
+    ///    rent-exempt lamports when shrinking
+    /// 3. `[]` The system program
+    ReallocateWithPayer {
+        data_length: u64,
+    },
 }
 
 impl<'a> RecordInstruction<'a> {
+    /// Unpacks a byte buffer into a [`RecordInstruction`].
+    ///
+    /// The buffer must hold exactly one encoded instruction, short buffers, length
+    /// prefixes pointing past the end and trailing bytes are all rejected.
     pub fn unpack(input: &'a [u8]) -> Result<Self, ProgramError> {
-        const U32_BYTES: usize = 4;
-        const U64_BYTES: usize = 8;
-
         let (&tag, rest) = input
             .split_first()
             .ok_or(ProgramError::InvalidInstructionData)?;
-        Ok(match tag {
-            0 => Self::Initialize,
+        let (instruction, rest) = match tag {
+            0 => (Self::Initialize, rest),
             1 => {
-                let offset = rest
-                    .get(..U64_BYTES)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
-                    .ok_or(ProgramError::InvalidInstructionData)?;
-
-                let (length, data) = rest[U64_BYTES..].split_at(U32_BYTES);
-                let length = u32::from_le_bytes(
-                    length
-                        .try_into()
-                        .map_err(|_| ProgramError::InvalidInstructionData)?,
-                ) as usize;
-
-                Self::Write {
-                    offset,
-                    data: &data[..length],
-                }
+                let (offset, rest) = unpack_u64(rest)?;
+                let (data, rest) = unpack_bytes(rest)?;
+                (Self::Write { offset, data }, rest)
             }
-            2 => Self::SetAuthority,
-            3 => Self::CloseAccount,
+            2 => (Self::SetAuthority, rest),
+            3 => (Self::CloseAccount, rest),
             4 => {
-                let data_length = rest
-                    .get(..U64_BYTES)
-                    .and_then(|slice| slice.try_into().ok())
-                    .map(u64::from_le_bytes)
+                let (data_length, rest) = unpack_u64(rest)?;
+                (Self::Reallocate { data_length }, rest)
+            }
+            5 => (Self::InitializeChecked, rest),
+            6 => {
+                let (data, rest) = unpack_bytes(rest)?;
+                (Self::InitializeWithData { data }, rest)
+            }
+            7 => {
+                let (data_length, rest) = unpack_u64(rest)?;
+                let (seed, rest) = unpack_bytes(rest)?;
+                (Self::CreateRecord { data_length, seed }, rest)
+            }
+            8 => {
+                let (data_length, rest) = unpack_u64(rest)?;
+                let (seed, rest) = unpack_bytes(rest)?;
+                (Self::CreateRecordPda { data_length, seed }, rest)
+            }
+            9 => (Self::ProposeAuthority, rest),
+            10 => (Self::AcceptAuthority, rest),
+            11 => (Self::CancelProposal, rest),
+            12 => (Self::Freeze, rest),
+            13 => {
+                let (with_payer, rest) = unpack_bool(rest)?;
+                (Self::Migrate { with_payer }, rest)
+            }
+            14 => {
+                let (m, rest) = unpack_u8(rest)?;
+                (Self::InitializeMultisig { m }, rest)
+            }
+            15 => {
+                let (start, rest) = unpack_u64(rest)?;
+                let (end, rest) = unpack_u64(rest)?;
+                let (expiry_slot, rest) = unpack_u64(rest)?;
+                (
+                    Self::ApproveDelegate {
+                        start,
+                        end,
+                        expiry_slot,
+                    },
+                    rest,
+                )
+            }
+            16 => (Self::RevokeDelegate, rest),
+            17 => {
+                let (data, rest) = unpack_bytes(rest)?;
+                let (with_payer, rest) = unpack_bool(rest)?;
+                (Self::Append { data, with_payer }, rest)
+            }
+            18 => {
+                let (expected_seq, rest) = unpack_u64(rest)?;
+                let (offset, rest) = unpack_u64(rest)?;
+                let (data, rest) = unpack_bytes(rest)?;
+                (
+                    Self::WriteIfVersion {
+                        expected_seq,
+                        offset,
+                        data,
+                    },
+                    rest,
+                )
+            }
+            19 => {
+                let (count, mut rest) = unpack_u8(rest)?;
+                if count == 0 {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let mut segments = Vec::with_capacity(count.into());
+                for _ in 0..count {
+                    let (offset, segment_rest) = unpack_u32(rest)?;
+                    let (length, segment_rest) = unpack_u16(segment_rest)?;
+                    if segment_rest.len() < usize::from(length) {
+                        return Err(ProgramError::InvalidInstructionData);
+                    }
+                    let (data, segment_rest) = segment_rest.split_at(length.into());
+                    segments.push((offset, data));
+                    rest = segment_rest;
+                }
+                (Self::WriteMany { segments }, rest)
+            }
+            20 => {
+                let (offset, rest) = unpack_u64(rest)?;
+                let (length, rest) = unpack_u64(rest)?;
+                let (byte, rest) = unpack_u8(rest)?;
+                (
+                    Self::Fill {
+                        offset,
+                        length,
+                        byte,
+                    },
+                    rest,
+                )
+            }
+            21 => {
+                let (src_offset, rest) = unpack_u64(rest)?;
+                let (dst_offset, rest) = unpack_u64(rest)?;
+                let (length, rest) = unpack_u64(rest)?;
+                (
+                    Self::CopyRange {
+                        src_offset,
+                        dst_offset,
+                        length,
+                    },
+                    rest,
+                )
+            }
+            22 => {
+                let (offset, rest) = unpack_u64(rest)?;
+                let (length, rest) = unpack_u64(rest)?;
+                let (delta, rest) = unpack_i64(rest)?;
+                let (with_payer, rest) = unpack_bool(rest)?;
+                (
+                    Self::Shift {
+                        offset,
+                        length,
+                        delta,
+                        with_payer,
+                    },
+                    rest,
+                )
+            }
+            23 => {
+                let (offset, rest) = unpack_u64(rest)?;
+                let (length, rest) = unpack_u64(rest)?;
+                let (algorithm, rest) = unpack_u8(rest)?;
+                let algorithm = HashAlgorithm::from_u8(algorithm)
                     .ok_or(ProgramError::InvalidInstructionData)?;
-
-                Self::Reallocate { data_length }
+                let (expected, rest) = unpack_hash(rest)?;
+                (
+                    Self::VerifyHash {
+                        offset,
+                        length,
+                        algorithm,
+                        expected,
+                    },
+                    rest,
+                )
+            }
+            24 => {
+                let (offset, rest) = unpack_u64(rest)?;
+                let (length, rest) = unpack_u64(rest)?;
+                (Self::Read { offset, length }, rest)
+            }
+            25 => {
+                let (data_length, rest) = unpack_u64(rest)?;
+                (Self::ReallocateWithPayer { data_length }, rest)
             }
             _ => return Err(ProgramError::InvalidInstructionData),
-        })
+        };
+        if !rest.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        Ok(instruction)
     }
 
     /// Packs a [`RecordInstruction`] into a byte buffer.
+    ///
+    /// # Panics
+    ///
+    /// Panics when a [`RecordInstruction::WriteMany`] holds more than `u8::MAX` segments
+    /// or a segment longer than `u16::MAX` bytes, which its encoding cannot represent.
     pub fn pack(&self) -> Vec<u8> {
         let mut buf = Vec::with_capacity(size_of::<Self>());
         match self {
@@ -71,7 +519,233 @@ impl<'a> RecordInstruction<'a> {
                 buf.push(4);
                 buf.extend_from_slice(&data_length.to_le_bytes());
             }
+            Self::InitializeChecked => buf.push(5),
+            Self::InitializeWithData { data } => {
+                buf.push(6);
+                buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::CreateRecord { data_length, seed } => {
+                buf.push(7);
+                buf.extend_from_slice(&data_length.to_le_bytes());
+                buf.extend_from_slice(&(seed.len() as u32).to_le_bytes());
+                buf.extend_from_slice(seed);
+            }
+            Self::CreateRecordPda { data_length, seed } => {
+                buf.push(8);
+                buf.extend_from_slice(&data_length.to_le_bytes());
+                buf.extend_from_slice(&(seed.len() as u32).to_le_bytes());
+                buf.extend_from_slice(seed);
+            }
+            Self::ProposeAuthority => buf.push(9),
+            Self::AcceptAuthority => buf.push(10),
+            Self::CancelProposal => buf.push(11),
+            Self::Freeze => buf.push(12),
+            Self::Migrate { with_payer } => {
+                buf.push(13);
+                buf.push(*with_payer as u8);
+            }
+            Self::InitializeMultisig { m } => {
+                buf.push(14);
+                buf.push(*m);
+            }
+            Self::ApproveDelegate {
+                start,
+                end,
+                expiry_slot,
+            } => {
+                buf.push(15);
+                buf.extend_from_slice(&start.to_le_bytes());
+                buf.extend_from_slice(&end.to_le_bytes());
+                buf.extend_from_slice(&expiry_slot.to_le_bytes());
+            }
+            Self::RevokeDelegate => buf.push(16),
+            Self::Append { data, with_payer } => {
+                buf.push(17);
+                buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                buf.extend_from_slice(data);
+                buf.push(*with_payer as u8);
+            }
+            Self::WriteIfVersion {
+                expected_seq,
+                offset,
+                data,
+            } => {
+                buf.push(18);
+                buf.extend_from_slice(&expected_seq.to_le_bytes());
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::WriteMany { segments } => {
+                buf.push(19);
+                let count = u8::try_from(segments.len()).expect("at most 255 segments");
+                buf.push(count);
+                for (offset, data) in segments {
+                    let length = u16::try_from(data.len()).expect("segment of at most 65535 bytes");
+                    buf.extend_from_slice(&offset.to_le_bytes());
+                    buf.extend_from_slice(&length.to_le_bytes());
+                    buf.extend_from_slice(data);
+                }
+            }
+            Self::Fill {
+                offset,
+                length,
+                byte,
+            } => {
+                buf.push(20);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&length.to_le_bytes());
+                buf.push(*byte);
+            }
+            Self::CopyRange {
+                src_offset,
+                dst_offset,
+                length,
+            } => {
+                buf.push(21);
+                buf.extend_from_slice(&src_offset.to_le_bytes());
+                buf.extend_from_slice(&dst_offset.to_le_bytes());
+                buf.extend_from_slice(&length.to_le_bytes());
+            }
+            Self::Shift {
+                offset,
+                length,
+                delta,
+                with_payer,
+            } => {
+                buf.push(22);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&length.to_le_bytes());
+                buf.extend_from_slice(&delta.to_le_bytes());
+                buf.push(*with_payer as u8);
+            }
+            Self::VerifyHash {
+                offset,
+                length,
+                algorithm,
+                expected,
+            } => {
+                buf.push(23);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&length.to_le_bytes());
+                buf.push(*algorithm as u8);
+                buf.extend_from_slice(expected);
+            }
+            Self::Read { offset, length } => {
+                buf.push(24);
+                buf.extend_from_slice(&offset.to_le_bytes());
+                buf.extend_from_slice(&length.to_le_bytes());
+            }
+            Self::ReallocateWithPayer { data_length } => {
+                buf.push(25);
+                buf.extend_from_slice(&data_length.to_le_bytes());
+            }
         };
         buf
     }
 }
+
+/// Length of the hashes checked by [`RecordInstruction::VerifyHash`]
+pub const HASH_BYTES: usize = 32;
+
+const U16_BYTES: usize = 2;
+const U32_BYTES: usize = 4;
+const U64_BYTES: usize = 8;
+
+fn unpack_u8(input: &[u8]) -> Result<(u8, &[u8]), ProgramError> {
+    let (&value, rest) = input
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((value, rest))
+}
+
+fn unpack_bool(input: &[u8]) -> Result<(bool, &[u8]), ProgramError> {
+    match input.split_first() {
+        Some((0, rest)) => Ok((false, rest)),
+        Some((1, rest)) => Ok((true, rest)),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+fn unpack_u16(input: &[u8]) -> Result<(u16, &[u8]), ProgramError> {
+    let value = input
+        .get(..U16_BYTES)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u16::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((value, &input[U16_BYTES..]))
+}
+
+fn unpack_u32(input: &[u8]) -> Result<(u32, &[u8]), ProgramError> {
+    let value = input
+        .get(..U32_BYTES)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u32::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((value, &input[U32_BYTES..]))
+}
+
+fn unpack_u64(input: &[u8]) -> Result<(u64, &[u8]), ProgramError> {
+    let value = input
+        .get(..U64_BYTES)
+        .and_then(|slice| slice.try_into().ok())
+        .map(u64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((value, &input[U64_BYTES..]))
+}
+
+fn unpack_i64(input: &[u8]) -> Result<(i64, &[u8]), ProgramError> {
+    let value = input
+        .get(..U64_BYTES)
+        .and_then(|slice| slice.try_into().ok())
+        .map(i64::from_le_bytes)
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((value, &input[U64_BYTES..]))
+}
+
+fn unpack_hash(input: &[u8]) -> Result<([u8; HASH_BYTES], &[u8]), ProgramError> {
+    let value = input
+        .get(..HASH_BYTES)
+        .and_then(|slice| slice.try_into().ok())
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    Ok((value, &input[HASH_BYTES..]))
+}
+
+/// Unpacks a `u32` length prefixed byte slice.
+fn unpack_bytes(input: &[u8]) -> Result<(&[u8], &[u8]), ProgramError> {
+    let (length, rest) = unpack_u32(input)?;
+    let length = usize::try_from(length).map_err(|_| ProgramError::InvalidInstructionData)?;
+    if rest.len() < length {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(rest.split_at(length))
+}
+
+/// Splits a resize of a record payload from `current_data_length` to `data_length`
+/// into steps that each stay within the runtime's per-instruction realloc limit, using
+/// [`RecordInstruction::ReallocateWithPayer`] when `with_payer` is set and
+/// [`RecordInstruction::Reallocate`] otherwise.
+pub fn reallocate_steps(
+    current_data_length: u64,
+    data_length: u64,
+    with_payer: bool,
+) -> Vec<RecordInstruction<'static>> {
+    let mut steps = Vec::new();
+    let mut length = current_data_length;
+    loop {
+        length = data_length.min(length.saturating_add(MAX_PERMITTED_DATA_INCREASE as u64));
+        steps.push(if with_payer {
+            RecordInstruction::ReallocateWithPayer {
+                data_length: length,
+            }
+        } else {
+            RecordInstruction::Reallocate {
+                data_length: length,
+            }
+        });
+        if length == data_length {
+            return steps;
+        }
+    }
+}
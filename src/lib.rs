@@ -12,6 +12,48 @@ pub const ID: Pubkey = [
     201, 137, 149, 229, 206, 232, 196, 28, 97, 114, 53,
 ];
 
+/// Seed prefix of records addressed by [`find_record_address`]
+pub const RECORD_SEED: &[u8] = b"record";
+
+/// Derives the address and bump seed of the record created by `authority` for `seed`
+/// with [`instruction::RecordInstruction::CreateRecordPda`].
+///
+/// The record header keeps neither the seed nor the bump, so the derivation is only
+/// checked when the record is created.
+pub fn find_record_address(authority: &Pubkey, seed: &[u8], program_id: &Pubkey) -> (Pubkey, u8) {
+    find_program_address(&[RECORD_SEED, authority, seed], program_id)
+}
+
+/// Seed prefix of delegate entries addressed by [`find_delegate_address`]
+pub const DELEGATE_SEED: &[u8] = b"delegate";
+
+/// Derives the address and bump seed of the entry holding the write permission of
+/// `delegate` on `record`, created with [`instruction::RecordInstruction::ApproveDelegate`].
+pub fn find_delegate_address(
+    record: &Pubkey,
+    delegate: &Pubkey,
+    program_id: &Pubkey,
+) -> (Pubkey, u8) {
+    find_program_address(&[DELEGATE_SEED, record, delegate], program_id)
+}
+
+/// Finds a program derived address, through the syscall on chain.
+fn find_program_address(seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+    #[cfg(target_os = "solana")]
+    {
+        pinocchio::pubkey::find_program_address(seeds, program_id)
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        let (address, bump) = solana_pubkey::Pubkey::find_program_address(
+            seeds,
+            &solana_pubkey::Pubkey::new_from_array(*program_id),
+        );
+        (address.to_bytes(), bump)
+    }
+}
+
 fn process_instruction(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -1,96 +1,607 @@
 use pinocchio::{
-    account_info::AccountInfo, get_account_info, program_error::ProgramError, pubkey::Pubkey,
+    account_info::{AccountInfo, MAX_PERMITTED_DATA_INCREASE},
+    get_account_info,
+    instruction::{Seed, Signer},
+    program::{set_return_data, MAX_RETURN_DATA},
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEED_LEN},
+    sysvars::{clock::Clock, rent::Rent, Sysvar},
     ProgramResult,
 };
 
-use crate::{error::RecordError, instruction::RecordInstruction, state::RecordData};
+use pinocchio_system::instructions::{
+    Allocate, Assign, CreateAccount, CreateAccountWithSeed, Transfer,
+};
+
+use crate::{
+    error::RecordError,
+    find_delegate_address, find_record_address,
+    instruction::{HashAlgorithm, RecordInstruction, HASH_BYTES},
+    state::{DelegateData, Multisig, RecordData, RecordDataV1},
+    DELEGATE_SEED, RECORD_SEED,
+};
 
-fn check_authority(authority_info: &AccountInfo, expected_authority: &Pubkey) -> ProgramResult {
+/// Checks that the expected authority approved the instruction, either by signing or,
+/// for a multisig authority, through `m` of its signers among `signers`.
+fn check_authority(
+    program_id: &Pubkey,
+    authority_info: &AccountInfo,
+    expected_authority: &Pubkey,
+    signers: &[AccountInfo],
+) -> ProgramResult {
     if expected_authority != authority_info.key() {
         return Err(RecordError::IncorrectAuthority.into());
     }
+    if authority_info.owner() == program_id && authority_info.data_len() == Multisig::LEN {
+        let raw_data = authority_info.try_borrow_data()?;
+        let multisig = bytemuck::try_from_bytes::<Multisig>(&raw_data)
+            .map_err(|_| ProgramError::InvalidArgument)?;
+        if multisig.is_initialized() {
+            let mut matched = [false; Multisig::MAX_SIGNERS];
+            let mut num_signers = 0_u8;
+            for signer in signers.iter().filter(|signer| signer.is_signer()) {
+                for (position, key) in multisig.signers().iter().enumerate() {
+                    if key == signer.key() && !matched[position] {
+                        matched[position] = true;
+                        num_signers += 1;
+                    }
+                }
+            }
+            if num_signers < multisig.m {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            return Ok(());
+        }
+    }
     if !authority_info.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     }
     Ok(())
 }
 
+/// Checks that `delegate_info` signed and that its entry, approved by the current authority
+/// of the record at `record` holding `account_data`, lets it write every `[start, end)`
+/// payload range in `slot`.
+fn check_delegate(
+    program_id: &Pubkey,
+    record: &Pubkey,
+    account_data: &RecordData,
+    delegate_info: &AccountInfo,
+    delegate_entry_info: &AccountInfo,
+    mut ranges: impl Iterator<Item = (u64, u64)>,
+    slot: u64,
+) -> ProgramResult {
+    check_owner(delegate_entry_info, program_id)?;
+    let raw_data = delegate_entry_info.try_borrow_data()?;
+    let delegate_data = bytemuck::try_from_bytes::<DelegateData>(&raw_data)
+        .map_err(|_| ProgramError::InvalidAccountData)?;
+    if !delegate_data.is_initialized() {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    // entries approved by a former authority, or for a record closed and created again
+    // since, no longer hold
+    if &delegate_data.record != record
+        || &delegate_data.delegate != delegate_info.key()
+        || delegate_data.authority != account_data.authority
+        || delegate_data.record_created_slot() != account_data.created_slot()
+    {
+        return Err(RecordError::IncorrectAuthority.into());
+    }
+    if !delegate_info.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    if delegate_data.is_expired(slot) {
+        return Err(RecordError::DelegateExpired.into());
+    }
+    if !ranges.all(|(start, end)| delegate_data.covers(start, end)) {
+        return Err(RecordError::DelegateOutOfRange.into());
+    }
+    Ok(())
+}
+
+/// Checks that the signer at `accounts[1]` may write every `[start, end)` payload range of
+/// the record at `accounts[0]`, as its authority or as a delegate whose entry follows.
+fn check_writer(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    account_data: &RecordData,
+    ranges: impl Iterator<Item = (u64, u64)>,
+    slot: u64,
+) -> ProgramResult {
+    let data_info = get_account_info!(accounts, 0);
+    let authority_info = get_account_info!(accounts, 1);
+    match accounts.get(2) {
+        Some(delegate_entry_info) if authority_info.key() != &account_data.authority => {
+            check_delegate(
+                program_id,
+                data_info.key(),
+                account_data,
+                authority_info,
+                delegate_entry_info,
+                ranges,
+                slot,
+            )
+        }
+        _ => check_authority(
+            program_id,
+            authority_info,
+            &account_data.authority,
+            &accounts[2..],
+        ),
+    }
+}
+
+fn check_owner(account_info: &AccountInfo, program_id: &Pubkey) -> ProgramResult {
+    if account_info.owner() != program_id {
+        return Err(RecordError::IncorrectOwner.into());
+    }
+    Ok(())
+}
+
+fn check_writable(account_info: &AccountInfo) -> ProgramResult {
+    if !account_info.is_writable() {
+        return Err(RecordError::AccountNotWritable.into());
+    }
+    Ok(())
+}
+
+/// Rejects record data that is not initialized with the current header version.
+fn check_current_version(raw_data: &[u8]) -> ProgramResult {
+    let version = raw_data
+        .first()
+        .copied()
+        .unwrap_or(RecordData::UNINITIALIZED_VERSION);
+    if version != RecordData::CURRENT_VERSION && RecordData::writable_start_index(version).is_some()
+    {
+        return Err(RecordError::OutdatedVersion.into());
+    }
+    if raw_data.len() < RecordData::WRITABLE_START_INDEX {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if version != RecordData::CURRENT_VERSION {
+        return Err(ProgramError::UninitializedAccount);
+    }
+    Ok(())
+}
+
+/// Returns the header of an initialized record.
+fn unpack_record(raw_data: &[u8]) -> Result<&RecordData, ProgramError> {
+    check_current_version(raw_data)?;
+    bytemuck::try_from_bytes::<RecordData>(&raw_data[..RecordData::WRITABLE_START_INDEX])
+        .map_err(|_| ProgramError::InvalidArgument)
+}
+
+/// Splits the data of an initialized record into its header and payload.
+fn unpack_record_mut(raw_data: &mut [u8]) -> Result<(&mut RecordData, &mut [u8]), ProgramError> {
+    check_current_version(raw_data)?;
+    let (header, payload) = raw_data.split_at_mut(RecordData::WRITABLE_START_INDEX);
+    let account_data = bytemuck::try_from_bytes_mut::<RecordData>(header)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    Ok((account_data, payload))
+}
+
+/// Returns the header of a record stored with the legacy v1 layout.
+fn unpack_record_v1(raw_data: &[u8]) -> Result<&RecordDataV1, ProgramError> {
+    let header = raw_data
+        .get(..RecordDataV1::WRITABLE_START_INDEX)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    bytemuck::try_from_bytes::<RecordDataV1>(header).map_err(|_| ProgramError::InvalidArgument)
+}
+
+/// Splits the data of a record stored with the legacy v1 layout into its header and
+/// payload.
+fn unpack_record_v1_mut(
+    raw_data: &mut [u8],
+) -> Result<(&mut RecordDataV1, &mut [u8]), ProgramError> {
+    if raw_data.len() < RecordDataV1::WRITABLE_START_INDEX {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let (header, payload) = raw_data.split_at_mut(RecordDataV1::WRITABLE_START_INDEX);
+    let account_data = bytemuck::try_from_bytes_mut::<RecordDataV1>(header)
+        .map_err(|_| ProgramError::InvalidArgument)?;
+    Ok((account_data, payload))
+}
+
+/// Returns the payload of an initialized record stored with any known header version.
+fn record_payload(raw_data: &[u8]) -> Result<&[u8], ProgramError> {
+    let version = raw_data
+        .first()
+        .copied()
+        .unwrap_or(RecordData::UNINITIALIZED_VERSION);
+    let start =
+        RecordData::writable_start_index(version).ok_or(ProgramError::UninitializedAccount)?;
+    raw_data
+        .get(start..)
+        .ok_or(ProgramError::InvalidAccountData)
+}
+
+/// Hashes `data` with `algorithm`, through the matching syscall on chain and the Solana
+/// hasher crates off chain.
+fn hash(algorithm: HashAlgorithm, data: &[u8]) -> [u8; HASH_BYTES] {
+    #[cfg(target_os = "solana")]
+    {
+        let mut hash = [0; HASH_BYTES];
+        let vals = [data];
+        let vals_addr = vals.as_ptr() as *const u8;
+        let vals_len = vals.len() as u64;
+        unsafe {
+            match algorithm {
+                HashAlgorithm::Sha256 => {
+                    pinocchio::syscalls::sol_sha256(vals_addr, vals_len, hash.as_mut_ptr())
+                }
+                HashAlgorithm::Keccak256 => {
+                    pinocchio::syscalls::sol_keccak256(vals_addr, vals_len, hash.as_mut_ptr())
+                }
+                #[cfg(feature = "blake3")]
+                HashAlgorithm::Blake3 => {
+                    pinocchio::syscalls::sol_blake3(vals_addr, vals_len, hash.as_mut_ptr())
+                }
+            };
+        }
+        hash
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    {
+        match algorithm {
+            HashAlgorithm::Sha256 => solana_sha256_hasher::hash(data).to_bytes(),
+            HashAlgorithm::Keccak256 => solana_keccak_hasher::hash(data).to_bytes(),
+            #[cfg(feature = "blake3")]
+            HashAlgorithm::Blake3 => solana_blake3_hasher::hash(data).to_bytes(),
+        }
+    }
+}
+
+/// Returns the authority of an initialized record stored with any known header version,
+/// and whether the record is frozen, for instructions needing nothing else from the header.
+fn unpack_authority(raw_data: &[u8]) -> Result<(Pubkey, bool), ProgramError> {
+    if raw_data.first() == Some(&RecordDataV1::VERSION) {
+        return Ok((unpack_record_v1(raw_data)?.authority, false));
+    }
+    let account_data = unpack_record(raw_data)?;
+    Ok((account_data.authority, account_data.is_frozen()))
+}
+
+/// Copies `data` into the record `payload` at `offset`, returning the end of the write.
+fn write_payload(payload: &mut [u8], offset: u64, data: &[u8]) -> Result<u64, ProgramError> {
+    let start = offset as usize;
+    let end = start.saturating_add(data.len());
+    if end > payload.len() {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    payload[start..end].copy_from_slice(data);
+    Ok(end as u64)
+}
+
+/// Tops the record up to the rent-exempt minimum of its current size from the payer at
+/// `accounts[2]`, followed by the system program at `accounts[3]`.
+fn pay_missing_rent(accounts: &[AccountInfo], data_info: &AccountInfo) -> ProgramResult {
+    let payer_info = get_account_info!(accounts, 2);
+    let system_program_info = get_account_info!(accounts, 3);
+    if payer_info.key() == data_info.key() {
+        return Err(ProgramError::InvalidArgument);
+    }
+    if system_program_info.key() != &pinocchio_system::ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    let minimum_balance = Rent::get()?.minimum_balance(data_info.data_len());
+    let missing_lamports = minimum_balance.saturating_sub(data_info.lamports());
+    if missing_lamports == 0 {
+        return Ok(());
+    }
+    Transfer {
+        from: payer_info,
+        to: data_info,
+        lamports: missing_lamports,
+    }
+    .invoke()
+}
+
+/// Returns the accounts after the fixed ones of an instruction taking an optional payer
+/// and system program, which are the multisig signers.
+fn signers_after_payer(accounts: &[AccountInfo], with_payer: bool) -> &[AccountInfo] {
+    let fixed_accounts = if with_payer { 4 } else { 2 };
+    accounts.get(fixed_accounts..).unwrap_or_default()
+}
+
+/// Creates a rent-exempt account of `space` bytes owned by the program at the program
+/// derived address signed for by `signers`.
+fn create_program_address_account(
+    program_id: &Pubkey,
+    payer_info: &AccountInfo,
+    account_info: &AccountInfo,
+    space: usize,
+    signers: &[Signer],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space);
+
+    // the address is predictable, so it may already hold lamports sent by anyone,
+    // which would make `CreateAccount` fail
+    let current_lamports = account_info.lamports();
+    if current_lamports == 0 {
+        return CreateAccount {
+            from: payer_info,
+            to: account_info,
+            lamports,
+            space: space as u64,
+            owner: program_id,
+        }
+        .invoke_signed(signers);
+    }
+
+    let missing_lamports = lamports.saturating_sub(current_lamports);
+    if missing_lamports > 0 {
+        Transfer {
+            from: payer_info,
+            to: account_info,
+            lamports: missing_lamports,
+        }
+        .invoke()?;
+    }
+    Allocate {
+        account: account_info,
+        space: space as u64,
+    }
+    .invoke_signed(signers)?;
+    Assign {
+        account: account_info,
+        owner: program_id,
+    }
+    .invoke_signed(signers)
+}
+
+/// Moves all lamports of a program account to the destination, wipes its data and hands
+/// it back to the system program so it cannot be revived and reinitialized later in the
+/// same transaction.
+fn close_program_account(
+    account_info: &AccountInfo,
+    destination_info: &AccountInfo,
+) -> ProgramResult {
+    {
+        // holding both borrows rejects a destination aliasing the account
+        let mut account_lamports = account_info.try_borrow_mut_lamports()?;
+        let mut destination_lamports = destination_info.try_borrow_mut_lamports()?;
+        *destination_lamports = destination_lamports
+            .checked_add(*account_lamports)
+            .ok_or(RecordError::Overflow)?;
+        *account_lamports = 0_u64;
+    }
+
+    account_info.try_borrow_mut_data()?.fill(0);
+    account_info.realloc(0, false)?;
+    account_info.assign(&pinocchio_system::ID);
+    Ok(())
+}
+
+fn initialize_created_record(
+    data_info: &AccountInfo,
+    authority_info: &AccountInfo,
+) -> ProgramResult {
+    let raw_data = &mut data_info.try_borrow_mut_data()?;
+    let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
+        &mut raw_data[..RecordData::WRITABLE_START_INDEX],
+    )
+    .map_err(|_| ProgramError::InvalidArgument)?;
+    *account_data = RecordData::new(authority_info.key(), Clock::get()?.slot);
+    Ok(())
+}
+
 pub fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     input: &[u8],
 ) -> ProgramResult {
     let instruction = RecordInstruction::unpack(input)?;
 
     match instruction {
-        RecordInstruction::Initialize => {
+        RecordInstruction::Initialize
+        | RecordInstruction::InitializeChecked
+        | RecordInstruction::InitializeWithData { .. } => {
             let data_info = get_account_info!(accounts, 0);
             let authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            if matches!(instruction, RecordInstruction::InitializeChecked) {
+                check_authority(
+                    program_id,
+                    authority_info,
+                    authority_info.key(),
+                    &accounts[2..],
+                )?;
+                if !Rent::get()?.is_exempt(data_info.lamports(), data_info.data_len()) {
+                    return Err(ProgramError::AccountNotRentExempt);
+                }
+            }
 
-            let raw_data = &mut data_info.try_borrow_mut_data().unwrap();
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
             if raw_data.len() < RecordData::WRITABLE_START_INDEX {
                 return Err(ProgramError::InvalidAccountData);
             }
 
-            let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
-                &mut raw_data[..RecordData::WRITABLE_START_INDEX],
-            )
-            .map_err(|_| ProgramError::InvalidArgument)?;
+            let (header, payload) = raw_data.split_at_mut(RecordData::WRITABLE_START_INDEX);
+            let account_data = bytemuck::try_from_bytes_mut::<RecordData>(header)
+                .map_err(|_| ProgramError::InvalidArgument)?;
 
-            if account_data.is_initialized() {
+            // records created with an older header version are not blank either
+            if account_data.version != RecordData::UNINITIALIZED_VERSION {
                 return Err(ProgramError::AccountAlreadyInitialized);
             }
 
-            account_data.authority = *authority_info.key();
-            account_data.version = RecordData::CURRENT_VERSION;
+            let slot = Clock::get()?.slot;
+            *account_data = RecordData::new(authority_info.key(), slot);
+
+            if let RecordInstruction::InitializeWithData { data } = instruction {
+                let end = write_payload(payload, 0, data)?;
+                account_data.record_write(end, slot);
+            }
 
             Ok(())
         }
 
-        RecordInstruction::Write { offset, data } => {
+        RecordInstruction::Write { offset, data }
+        | RecordInstruction::WriteIfVersion { offset, data, .. } => {
             let data_info = get_account_info!(accounts, 0);
-            let authority_info = get_account_info!(accounts, 1);
-            {
-                let raw_data = &data_info.try_borrow_data().unwrap();
-                if raw_data.len() < RecordData::WRITABLE_START_INDEX {
-                    return Err(ProgramError::InvalidAccountData);
-                }
-                let account_data = bytemuck::try_from_bytes::<RecordData>(
-                    &raw_data[..RecordData::WRITABLE_START_INDEX],
-                )
-                .map_err(|_| ProgramError::InvalidArgument)?;
-                if !account_data.is_initialized() {
-                    return Err(ProgramError::UninitializedAccount);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            if raw_data.first() == Some(&RecordDataV1::VERSION) {
+                // records still on the v1 header keep taking plain writes, but have no
+                // write count to compare against
+                if matches!(instruction, RecordInstruction::WriteIfVersion { .. }) {
+                    return Err(RecordError::OutdatedVersion.into());
                 }
-                check_authority(authority_info, &account_data.authority)?;
+                let authority_info = get_account_info!(accounts, 1);
+                let (account_data, payload) = unpack_record_v1_mut(raw_data)?;
+                check_authority(
+                    program_id,
+                    authority_info,
+                    &account_data.authority,
+                    &accounts[2..],
+                )?;
+                write_payload(payload, offset, data)?;
+                return Ok(());
             }
-            let start = RecordData::WRITABLE_START_INDEX.saturating_add(offset as usize);
-            let end = start.saturating_add(data.len());
-            if end > data_info.try_borrow_data().unwrap().len() {
-                Err(ProgramError::AccountDataTooSmall)
-            } else {
-                data_info.try_borrow_mut_data().unwrap()[start..end].copy_from_slice(data);
-                Ok(())
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
             }
+            let slot = Clock::get()?.slot;
+            check_writer(
+                program_id,
+                accounts,
+                account_data,
+                core::iter::once((offset, offset.saturating_add(data.len() as u64))),
+                slot,
+            )?;
+            if let RecordInstruction::WriteIfVersion { expected_seq, .. } = instruction {
+                if account_data.write_count() != expected_seq {
+                    return Err(RecordError::WriteConflict.into());
+                }
+            }
+            let end = write_payload(payload, offset, data)?;
+            account_data.record_write(end, slot);
+            Ok(())
         }
 
         RecordInstruction::SetAuthority => {
             let data_info = get_account_info!(accounts, 0);
             let authority_info = get_account_info!(accounts, 1);
             let new_authority_info = get_account_info!(accounts, 2);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
             let raw_data = &mut data_info.try_borrow_mut_data()?;
-            if raw_data.len() < RecordData::WRITABLE_START_INDEX {
-                return Err(ProgramError::InvalidAccountData);
+            if raw_data.first() == Some(&RecordDataV1::VERSION) {
+                let (account_data, _) = unpack_record_v1_mut(raw_data)?;
+                check_authority(
+                    program_id,
+                    authority_info,
+                    &account_data.authority,
+                    &accounts[3..],
+                )?;
+                account_data.authority = *new_authority_info.key();
+                return Ok(());
             }
-            let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
-                &mut raw_data[..RecordData::WRITABLE_START_INDEX],
-            )
-            .map_err(|_| ProgramError::InvalidArgument)?;
-            if !account_data.is_initialized() {
-                return Err(ProgramError::UninitializedAccount);
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
             }
-            check_authority(authority_info, &account_data.authority)?;
+            check_authority(
+                program_id,
+                authority_info,
+                &account_data.authority,
+                &accounts[3..],
+            )?;
             account_data.authority = *new_authority_info.key();
+            account_data.pending_authority = Pubkey::default();
+            Ok(())
+        }
+
+        RecordInstruction::ProposeAuthority => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            let pending_authority_info = get_account_info!(accounts, 2);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
+            }
+            check_authority(
+                program_id,
+                authority_info,
+                &account_data.authority,
+                &accounts[3..],
+            )?;
+            account_data.pending_authority = *pending_authority_info.key();
+            Ok(())
+        }
+
+        RecordInstruction::AcceptAuthority => {
+            let data_info = get_account_info!(accounts, 0);
+            let pending_authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
+            }
+            if !account_data.has_pending_authority() {
+                return Err(RecordError::NoPendingAuthority.into());
+            }
+            check_authority(
+                program_id,
+                pending_authority_info,
+                &account_data.pending_authority,
+                &accounts[2..],
+            )?;
+            account_data.authority = account_data.pending_authority;
+            account_data.pending_authority = Pubkey::default();
+            Ok(())
+        }
+
+        RecordInstruction::CancelProposal => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
+            }
+            check_authority(
+                program_id,
+                authority_info,
+                &account_data.authority,
+                &accounts[2..],
+            )?;
+            if !account_data.has_pending_authority() {
+                return Err(RecordError::NoPendingAuthority.into());
+            }
+            account_data.pending_authority = Pubkey::default();
+            Ok(())
+        }
+
+        RecordInstruction::Freeze => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, _) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
+            }
+            check_authority(
+                program_id,
+                authority_info,
+                &account_data.authority,
+                &accounts[2..],
+            )?;
+            account_data.flags |= RecordData::FROZEN_FLAG;
+            account_data.pending_authority = Pubkey::default();
             Ok(())
         }
 
@@ -98,56 +609,635 @@ pub fn process_instruction(
             let data_info = get_account_info!(accounts, 0);
             let authority_info = get_account_info!(accounts, 1);
             let destination_info = get_account_info!(accounts, 2);
-            let raw_data = &mut data_info.try_borrow_mut_data()?;
-            if raw_data.len() < RecordData::WRITABLE_START_INDEX {
-                return Err(ProgramError::InvalidAccountData);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            {
+                let raw_data = &data_info.try_borrow_data()?;
+                let (authority, frozen) = unpack_authority(raw_data)?;
+                if frozen {
+                    return Err(RecordError::Frozen.into());
+                }
+                check_authority(program_id, authority_info, &authority, &accounts[3..])?;
             }
-            let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
-                &mut raw_data[..RecordData::WRITABLE_START_INDEX],
-            )
-            .map_err(|_| ProgramError::InvalidArgument)?;
-            if !account_data.is_initialized() {
-                return Err(ProgramError::UninitializedAccount);
-            }
-            check_authority(authority_info, &account_data.authority)?;
-            let destination_starting_lamports = *destination_info.try_borrow_lamports()?;
-            let data_lamports = *data_info.try_borrow_lamports()?;
-            *destination_info.try_borrow_mut_lamports().unwrap() = destination_starting_lamports
-                .checked_add(data_lamports)
+            close_program_account(data_info, destination_info)
+        }
+
+        RecordInstruction::Reallocate { data_length }
+        | RecordInstruction::ReallocateWithPayer { data_length } => {
+            let with_payer = matches!(instruction, RecordInstruction::ReallocateWithPayer { .. });
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let needed_account_length = std::mem::size_of::<RecordData>()
+                .checked_add(
+                    usize::try_from(data_length).map_err(|_| ProgramError::InvalidArgument)?,
+                )
                 .ok_or(RecordError::Overflow)?;
-            *data_info.try_borrow_mut_lamports().unwrap() = 0_u64;
+            // multisig signers follow the destination when shrinking without a payer
+            let signers = if with_payer {
+                accounts.get(4..).unwrap_or_default()
+            } else if data_info.data_len() > needed_account_length {
+                accounts.get(3..).unwrap_or_default()
+            } else {
+                accounts.get(2..).unwrap_or_default()
+            };
+            {
+                let raw_data = &data_info.try_borrow_data()?;
+                let account_data = unpack_record(raw_data)?;
+                if account_data.is_frozen() {
+                    return Err(RecordError::Frozen.into());
+                }
+                check_authority(program_id, authority_info, &account_data.authority, signers)?;
+            }
+
+            if data_info.data_len() < needed_account_length {
+                // grow at most by the runtime limit, the client repeats the instruction
+                // until the target length is reached
+                let new_account_length = needed_account_length.min(
+                    data_info
+                        .data_len()
+                        .saturating_add(MAX_PERMITTED_DATA_INCREASE),
+                );
+                data_info.realloc(new_account_length, false)?;
+                if with_payer {
+                    pay_missing_rent(accounts, data_info)?;
+                }
+            } else if data_info.data_len() > needed_account_length {
+                let destination_info = get_account_info!(accounts, 2);
+                data_info.realloc(needed_account_length, false)?;
+                {
+                    let raw_data = &mut data_info.try_borrow_mut_data()?;
+                    let (account_data, _) = unpack_record_mut(raw_data)?;
+                    account_data.record_shrink(data_length, Clock::get()?.slot);
+                }
+
+                let minimum_balance = Rent::get()?.minimum_balance(needed_account_length);
+                let mut data_lamports = data_info.try_borrow_mut_lamports()?;
+                let mut destination_lamports = destination_info.try_borrow_mut_lamports()?;
+                let excess_lamports = data_lamports.saturating_sub(minimum_balance);
+                *destination_lamports = destination_lamports
+                    .checked_add(excess_lamports)
+                    .ok_or(RecordError::Overflow)?;
+                *data_lamports -= excess_lamports;
+            }
+
+            // set last, the rent transfer CPI clears any return data set before it
+            set_return_data(
+                &((data_info.data_len() - RecordData::WRITABLE_START_INDEX) as u64).to_le_bytes(),
+            );
             Ok(())
         }
 
-        RecordInstruction::Reallocate { data_length } => {
+        RecordInstruction::CreateRecord { data_length, seed } => {
             let data_info = get_account_info!(accounts, 0);
             let authority_info = get_account_info!(accounts, 1);
-            {
-                let raw_data = &mut data_info.try_borrow_mut_data().unwrap();
-                if raw_data.len() < RecordData::WRITABLE_START_INDEX {
-                    return Err(ProgramError::InvalidAccountData);
+            let payer_info = get_account_info!(accounts, 2);
+            let system_program_info = get_account_info!(accounts, 3);
+            if system_program_info.key() != &pinocchio_system::ID {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+
+            let account_length = std::mem::size_of::<RecordData>()
+                .checked_add(
+                    usize::try_from(data_length).map_err(|_| ProgramError::InvalidArgument)?,
+                )
+                .ok_or(RecordError::Overflow)?;
+            let lamports = Rent::get()?.minimum_balance(account_length);
+
+            if seed.is_empty() {
+                CreateAccount {
+                    from: payer_info,
+                    to: data_info,
+                    lamports,
+                    space: account_length as u64,
+                    owner: program_id,
+                }
+                .invoke()?;
+            } else {
+                if seed.len() > MAX_SEED_LEN {
+                    return Err(ProgramError::MaxSeedLengthExceeded);
                 }
+                CreateAccountWithSeed {
+                    from: payer_info,
+                    to: data_info,
+                    base: Some(authority_info),
+                    seed: core::str::from_utf8(seed)
+                        .map_err(|_| ProgramError::InvalidInstructionData)?,
+                    lamports,
+                    space: account_length as u64,
+                    owner: program_id,
+                }
+                .invoke()?;
+            }
+
+            initialize_created_record(data_info, authority_info)
+        }
+
+        RecordInstruction::CreateRecordPda { data_length, seed } => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            let payer_info = get_account_info!(accounts, 2);
+            let system_program_info = get_account_info!(accounts, 3);
+            if system_program_info.key() != &pinocchio_system::ID {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            check_authority(
+                program_id,
+                authority_info,
+                authority_info.key(),
+                &accounts[4..],
+            )?;
+            if seed.len() > MAX_SEED_LEN {
+                return Err(ProgramError::MaxSeedLengthExceeded);
+            }
+            let (record_address, bump) =
+                find_record_address(authority_info.key(), seed, program_id);
+            if data_info.key() != &record_address {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            let account_length = std::mem::size_of::<RecordData>()
+                .checked_add(
+                    usize::try_from(data_length).map_err(|_| ProgramError::InvalidArgument)?,
+                )
+                .ok_or(RecordError::Overflow)?;
+
+            let bump = [bump];
+            let signer_seeds = [
+                Seed::from(RECORD_SEED),
+                Seed::from(authority_info.key()),
+                Seed::from(seed),
+                Seed::from(&bump),
+            ];
+            create_program_address_account(
+                program_id,
+                payer_info,
+                data_info,
+                account_length,
+                &[Signer::from(&signer_seeds)],
+            )?;
+
+            initialize_created_record(data_info, authority_info)
+        }
+
+        RecordInstruction::Migrate { with_payer } => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let signers = signers_after_payer(accounts, with_payer);
+            let (authority, account_length) = {
+                let raw_data = &data_info.try_borrow_data()?;
+                if raw_data.first() != Some(&RecordDataV1::VERSION) {
+                    let account_data = unpack_record(raw_data)?;
+                    check_authority(program_id, authority_info, &account_data.authority, signers)?;
+                    return Ok(());
+                }
+                let account_data = unpack_record_v1(raw_data)?;
+                check_authority(program_id, authority_info, &account_data.authority, signers)?;
+                (account_data.authority, raw_data.len())
+            };
+
+            let new_account_length = account_length
+                .checked_add(RecordData::WRITABLE_START_INDEX - RecordDataV1::WRITABLE_START_INDEX)
+                .ok_or(RecordError::Overflow)?;
+            data_info.realloc(new_account_length, false)?;
+            {
+                let raw_data = &mut data_info.try_borrow_mut_data()?;
+                raw_data.copy_within(
+                    RecordDataV1::WRITABLE_START_INDEX..account_length,
+                    RecordData::WRITABLE_START_INDEX,
+                );
                 let account_data = bytemuck::try_from_bytes_mut::<RecordData>(
                     &mut raw_data[..RecordData::WRITABLE_START_INDEX],
                 )
                 .map_err(|_| ProgramError::InvalidArgument)?;
+                // how much of the old payload was written is unknown, so all of it counts
+                *account_data = RecordData::new(&authority, Clock::get()?.slot);
+                account_data
+                    .set_data_len((account_length - RecordDataV1::WRITABLE_START_INDEX) as u64);
+            }
+
+            if with_payer {
+                pay_missing_rent(accounts, data_info)?;
+            }
+            Ok(())
+        }
+
+        RecordInstruction::InitializeMultisig { m } => {
+            let multisig_info = get_account_info!(accounts, 0);
+            let signer_infos = &accounts[1..];
+            check_owner(multisig_info, program_id)?;
+            check_writable(multisig_info)?;
+            if !Rent::get()?.is_exempt(multisig_info.lamports(), multisig_info.data_len()) {
+                return Err(ProgramError::AccountNotRentExempt);
+            }
+
+            let raw_data = &mut multisig_info.try_borrow_mut_data()?;
+            if raw_data.len() != Multisig::LEN {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            let multisig = bytemuck::try_from_bytes_mut::<Multisig>(raw_data)
+                .map_err(|_| ProgramError::InvalidArgument)?;
+            if multisig.version != RecordData::UNINITIALIZED_VERSION {
+                return Err(ProgramError::AccountAlreadyInitialized);
+            }
+
+            if !(Multisig::MIN_SIGNERS..=Multisig::MAX_SIGNERS).contains(&signer_infos.len()) {
+                return Err(RecordError::InvalidNumberOfProvidedSigners.into());
+            }
+            if !(1..=signer_infos.len()).contains(&usize::from(m)) {
+                return Err(RecordError::InvalidNumberOfRequiredSigners.into());
+            }
+            for (position, signer_info) in signer_infos.iter().enumerate() {
+                // a repeated signer would count more than once towards `m`
+                if signer_infos[..position]
+                    .iter()
+                    .any(|other| other.key() == signer_info.key())
+                {
+                    return Err(RecordError::InvalidNumberOfProvidedSigners.into());
+                }
+                multisig.signers[position] = *signer_info.key();
+            }
+            multisig.m = m;
+            multisig.n = signer_infos.len() as u8;
+            multisig.version = Multisig::CURRENT_VERSION;
+            Ok(())
+        }
+
+        RecordInstruction::ApproveDelegate {
+            start,
+            end,
+            expiry_slot,
+        } => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            let delegate_entry_info = get_account_info!(accounts, 2);
+            let delegate_info = get_account_info!(accounts, 3);
+            let payer_info = get_account_info!(accounts, 4);
+            let system_program_info = get_account_info!(accounts, 5);
+            check_owner(data_info, program_id)?;
+            let (authority, created_slot) = {
+                let raw_data = &data_info.try_borrow_data()?;
+                let account_data = unpack_record(raw_data)?;
+                if account_data.is_frozen() {
+                    return Err(RecordError::Frozen.into());
+                }
+                check_authority(
+                    program_id,
+                    authority_info,
+                    &account_data.authority,
+                    &accounts[6..],
+                )?;
+                (account_data.authority, account_data.created_slot())
+            };
+            if start > end {
+                return Err(ProgramError::InvalidArgument);
+            }
+            if system_program_info.key() != &pinocchio_system::ID {
+                return Err(ProgramError::IncorrectProgramId);
+            }
+            check_writable(delegate_entry_info)?;
+            let (delegate_entry_address, bump) =
+                find_delegate_address(data_info.key(), delegate_info.key(), program_id);
+            if delegate_entry_info.key() != &delegate_entry_address {
+                return Err(ProgramError::InvalidSeeds);
+            }
+
+            if delegate_entry_info.owner() != program_id {
+                let bump = [bump];
+                let signer_seeds = [
+                    Seed::from(DELEGATE_SEED),
+                    Seed::from(data_info.key()),
+                    Seed::from(delegate_info.key()),
+                    Seed::from(&bump),
+                ];
+                create_program_address_account(
+                    program_id,
+                    payer_info,
+                    delegate_entry_info,
+                    DelegateData::LEN,
+                    &[Signer::from(&signer_seeds)],
+                )?;
+            }
+
+            let raw_data = &mut delegate_entry_info.try_borrow_mut_data()?;
+            let delegate_data = bytemuck::try_from_bytes_mut::<DelegateData>(raw_data)
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            *delegate_data = DelegateData::new(
+                data_info.key(),
+                delegate_info.key(),
+                &authority,
+                created_slot,
+                start,
+                end,
+                expiry_slot,
+            );
+            Ok(())
+        }
 
-                if !account_data.is_initialized() {
+        RecordInstruction::RevokeDelegate => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            let delegate_entry_info = get_account_info!(accounts, 2);
+            let destination_info = get_account_info!(accounts, 3);
+            check_owner(delegate_entry_info, program_id)?;
+            check_writable(delegate_entry_info)?;
+            {
+                let raw_data = &delegate_entry_info.try_borrow_data()?;
+                let delegate_data = bytemuck::try_from_bytes::<DelegateData>(raw_data)
+                    .map_err(|_| ProgramError::InvalidAccountData)?;
+                if !delegate_data.is_initialized() {
                     return Err(ProgramError::UninitializedAccount);
                 }
-                check_authority(authority_info, &account_data.authority)?;
+                if &delegate_data.record != data_info.key() {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                // once the record is closed, the authority that approved the permission
+                // revokes it in place of the record authority
+                let authority = if data_info.owner() == program_id {
+                    let raw_data = &data_info.try_borrow_data()?;
+                    unpack_record(raw_data)?.authority
+                } else {
+                    delegate_data.authority
+                };
+                check_authority(program_id, authority_info, &authority, &accounts[4..])?;
             }
 
-            let needed_account_length = std::mem::size_of::<RecordData>()
-                .checked_add(
-                    usize::try_from(data_length).map_err(|_| ProgramError::InvalidArgument)?,
-                )
-                .unwrap();
+            close_program_account(delegate_entry_info, destination_info)
+        }
+
+        RecordInstruction::Append { data, with_payer } => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let (start, end) = {
+                let raw_data = &data_info.try_borrow_data()?;
+                let account_data = unpack_record(raw_data)?;
+                if account_data.is_frozen() {
+                    return Err(RecordError::Frozen.into());
+                }
+                check_authority(
+                    program_id,
+                    authority_info,
+                    &account_data.authority,
+                    signers_after_payer(accounts, with_payer),
+                )?;
+                let start = account_data.data_len();
+                let end = start
+                    .checked_add(data.len() as u64)
+                    .ok_or(RecordError::Overflow)?;
+                (start, end)
+            };
+
+            let needed_account_length = RecordData::WRITABLE_START_INDEX
+                .checked_add(usize::try_from(end).map_err(|_| ProgramError::InvalidArgument)?)
+                .ok_or(RecordError::Overflow)?;
+            if data_info.data_len() < needed_account_length {
+                if !with_payer {
+                    return Err(RecordError::RecordFull.into());
+                }
+                data_info.realloc(needed_account_length, false)?;
+                pay_missing_rent(accounts, data_info)?;
+            }
+
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            payload[start as usize..end as usize].copy_from_slice(data);
+            account_data.record_write(end, Clock::get()?.slot);
+            set_return_data(&start.to_le_bytes());
+            Ok(())
+        }
+
+        RecordInstruction::WriteMany { segments } => {
+            let data_info = get_account_info!(accounts, 0);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
+            }
+            let slot = Clock::get()?.slot;
+            check_writer(
+                program_id,
+                accounts,
+                account_data,
+                segments.iter().map(|(offset, data)| {
+                    let start = u64::from(*offset);
+                    (start, start.saturating_add(data.len() as u64))
+                }),
+                slot,
+            )?;
+
+            // check every segment first so that none is applied when one does not fit
+            let mut end = 0;
+            for (index, (offset, data)) in segments.iter().enumerate() {
+                let start = *offset as usize;
+                let segment_end = start.saturating_add(data.len());
+                if segment_end > payload.len() {
+                    return Err(ProgramError::AccountDataTooSmall);
+                }
+                // overlapping segments would make the result depend on their order, empty
+                // ones write nothing and overlap none
+                if !data.is_empty()
+                    && segments[..index].iter().any(|(other_offset, other_data)| {
+                        let other_start = *other_offset as usize;
+                        !other_data.is_empty()
+                            && start < other_start + other_data.len()
+                            && other_start < segment_end
+                    })
+                {
+                    return Err(ProgramError::InvalidArgument);
+                }
+                end = end.max(segment_end);
+            }
+            for (offset, data) in &segments {
+                let start = *offset as usize;
+                payload[start..start + data.len()].copy_from_slice(data);
+            }
+            account_data.record_write(end as u64, slot);
+            Ok(())
+        }
 
-            if data_info.data_len() >= needed_account_length {
+        RecordInstruction::Fill {
+            offset,
+            length,
+            byte,
+        } => {
+            let data_info = get_account_info!(accounts, 0);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
+            }
+            let slot = Clock::get()?.slot;
+            check_writer(
+                program_id,
+                accounts,
+                account_data,
+                core::iter::once((offset, offset.saturating_add(length))),
+                slot,
+            )?;
+            let start = offset as usize;
+            let end = start.saturating_add(length as usize);
+            if end > payload.len() {
+                return Err(ProgramError::AccountDataTooSmall);
+            }
+            payload[start..end].fill(byte);
+            account_data.record_write(end as u64, slot);
+            Ok(())
+        }
+
+        RecordInstruction::CopyRange {
+            src_offset,
+            dst_offset,
+            length,
+        } => {
+            let source_info = get_account_info!(accounts, 0);
+            let data_info = get_account_info!(accounts, 1);
+            let authority_info = get_account_info!(accounts, 2);
+            check_owner(source_info, program_id)?;
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let src_start = src_offset as usize;
+            let src_end = src_start.saturating_add(length as usize);
+            let dst_start = dst_offset as usize;
+            let dst_end = dst_start.saturating_add(length as usize);
+            let slot = Clock::get()?.slot;
+
+            if source_info.key() == data_info.key() {
+                // a record cannot be borrowed twice, and the ranges may overlap
+                let raw_data = &mut data_info.try_borrow_mut_data()?;
+                let (account_data, payload) = unpack_record_mut(raw_data)?;
+                if account_data.is_frozen() {
+                    return Err(RecordError::Frozen.into());
+                }
+                check_authority(
+                    program_id,
+                    authority_info,
+                    &account_data.authority,
+                    &accounts[3..],
+                )?;
+                if src_end > payload.len() || dst_end > payload.len() {
+                    return Err(ProgramError::AccountDataTooSmall);
+                }
+                payload.copy_within(src_start..src_end, dst_start);
+                account_data.record_write(dst_end as u64, slot);
                 return Ok(());
             }
-            data_info.realloc(needed_account_length, false)?;
+
+            let source_data = &source_info.try_borrow_data()?;
+            let source_payload = record_payload(source_data)?;
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            if account_data.is_frozen() {
+                return Err(RecordError::Frozen.into());
+            }
+            check_authority(
+                program_id,
+                authority_info,
+                &account_data.authority,
+                &accounts[3..],
+            )?;
+            if src_end > source_payload.len() || dst_end > payload.len() {
+                return Err(ProgramError::AccountDataTooSmall);
+            }
+            payload[dst_start..dst_end].copy_from_slice(&source_payload[src_start..src_end]);
+            account_data.record_write(dst_end as u64, slot);
+            Ok(())
+        }
+
+        RecordInstruction::Shift {
+            offset,
+            length,
+            delta,
+            with_payer,
+        } => {
+            let data_info = get_account_info!(accounts, 0);
+            let authority_info = get_account_info!(accounts, 1);
+            check_owner(data_info, program_id)?;
+            check_writable(data_info)?;
+            let src_end = offset.checked_add(length).ok_or(RecordError::Overflow)?;
+            let dst_start = offset
+                .checked_add_signed(delta)
+                .ok_or(ProgramError::InvalidArgument)?;
+            let dst_end = dst_start.checked_add(length).ok_or(RecordError::Overflow)?;
+            {
+                let raw_data = &data_info.try_borrow_data()?;
+                let account_data = unpack_record(raw_data)?;
+                if account_data.is_frozen() {
+                    return Err(RecordError::Frozen.into());
+                }
+                check_authority(
+                    program_id,
+                    authority_info,
+                    &account_data.authority,
+                    signers_after_payer(accounts, with_payer),
+                )?;
+                if src_end > (raw_data.len() - RecordData::WRITABLE_START_INDEX) as u64 {
+                    return Err(ProgramError::AccountDataTooSmall);
+                }
+            }
+
+            let needed_account_length = RecordData::WRITABLE_START_INDEX
+                .checked_add(usize::try_from(dst_end).map_err(|_| ProgramError::InvalidArgument)?)
+                .ok_or(RecordError::Overflow)?;
+            if data_info.data_len() < needed_account_length {
+                if !with_payer {
+                    return Err(ProgramError::AccountDataTooSmall);
+                }
+                data_info.realloc(needed_account_length, false)?;
+                pay_missing_rent(accounts, data_info)?;
+            }
+
+            let raw_data = &mut data_info.try_borrow_mut_data()?;
+            let (account_data, payload) = unpack_record_mut(raw_data)?;
+            payload.copy_within(offset as usize..src_end as usize, dst_start as usize);
+            let data_len = account_data.data_len();
+            account_data.record_write(dst_end, Clock::get()?.slot);
+            if offset < data_len && data_len <= src_end {
+                account_data.set_data_len(dst_end);
+            }
+            Ok(())
+        }
+
+        RecordInstruction::VerifyHash {
+            offset,
+            length,
+            algorithm,
+            expected,
+        } => {
+            let data_info = get_account_info!(accounts, 0);
+            check_owner(data_info, program_id)?;
+            let raw_data = &data_info.try_borrow_data()?;
+            let payload = record_payload(raw_data)?;
+            let start = offset as usize;
+            let end = start.saturating_add(length as usize);
+            if end > payload.len() {
+                return Err(ProgramError::AccountDataTooSmall);
+            }
+            if hash(algorithm, &payload[start..end]) != expected {
+                return Err(RecordError::HashMismatch.into());
+            }
+            Ok(())
+        }
+
+        RecordInstruction::Read { offset, length } => {
+            let data_info = get_account_info!(accounts, 0);
+            check_owner(data_info, program_id)?;
+            let raw_data = &data_info.try_borrow_data()?;
+            let payload = record_payload(raw_data)?;
+            let start = offset as usize;
+            let end = start.saturating_add((length as usize).min(MAX_RETURN_DATA));
+            if end > payload.len() {
+                return Err(ProgramError::AccountDataTooSmall);
+            }
+            set_return_data(&payload[start..end]);
             Ok(())
         }
     }
@@ -1,5 +1,6 @@
 use bytemuck::{Pod, Zeroable};
 use pinocchio::pubkey::Pubkey;
+use std::mem::size_of;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
@@ -9,18 +10,264 @@ pub struct RecordData {
 
     /// The account allowed to update the data
     pub authority: Pubkey,
+
+    /// The account proposed to become the authority, all zeros when there is none
+    pub pending_authority: Pubkey,
+
+    /// Bit set of record flags, see [`RecordData::FROZEN_FLAG`]
+    pub flags: u8,
+
+    /// Number of meaningful payload bytes, the end of the furthest write
+    pub data_len: [u8; 8],
+
+    /// Slot of the last write to the payload
+    pub last_write_slot: [u8; 8],
+
+    /// Number of writes to the payload, shrinking reallocations included
+    pub write_count: [u8; 8],
+
+    /// Slot in which the record was initialized or migrated to this header version
+    pub created_slot: [u8; 8],
 }
 
 impl RecordData {
     /// Version to fill in on new created accounts
-    pub const CURRENT_VERSION: u8 = 1;
+    pub const CURRENT_VERSION: u8 = 2;
 
-    /// Start of writable account data, after version and authority
-    pub const WRITABLE_START_INDEX: usize = 33;
+    /// Version of accounts that were never initialized
+    pub const UNINITIALIZED_VERSION: u8 = 0;
+
+    /// Flag of records that can never be modified again
+    pub const FROZEN_FLAG: u8 = 1;
+
+    /// Start of writable account data, right after the header
+    pub const WRITABLE_START_INDEX: usize = size_of::<Self>();
+
+    /// Start of writable account data of records stored with `version`, `None` for
+    /// uninitialized records and versions unknown to this program
+    pub fn writable_start_index(version: u8) -> Option<usize> {
+        match version {
+            RecordDataV1::VERSION => Some(RecordDataV1::WRITABLE_START_INDEX),
+            Self::CURRENT_VERSION => Some(Self::WRITABLE_START_INDEX),
+            _ => None,
+        }
+    }
 }
 
 impl RecordData {
+    /// Header of a record controlled by `authority`, initialized in `slot`
+    pub fn new(authority: &Pubkey, slot: u64) -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            authority: *authority,
+            created_slot: slot.to_le_bytes(),
+            ..Self::zeroed()
+        }
+    }
+
+    /// Whether the record was initialized with any known header version
+    pub fn is_initialized(&self) -> bool {
+        Self::writable_start_index(self.version).is_some()
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.flags & Self::FROZEN_FLAG != 0
+    }
+
+    pub fn has_pending_authority(&self) -> bool {
+        self.pending_authority != Pubkey::default()
+    }
+
+    pub fn data_len(&self) -> u64 {
+        u64::from_le_bytes(self.data_len)
+    }
+
+    pub fn set_data_len(&mut self, data_len: u64) {
+        self.data_len = data_len.to_le_bytes();
+    }
+
+    pub fn last_write_slot(&self) -> u64 {
+        u64::from_le_bytes(self.last_write_slot)
+    }
+
+    pub fn write_count(&self) -> u64 {
+        u64::from_le_bytes(self.write_count)
+    }
+
+    pub fn created_slot(&self) -> u64 {
+        u64::from_le_bytes(self.created_slot)
+    }
+
+    /// Updates the bookkeeping after a payload write ending at `end` in `slot`.
+    pub fn record_write(&mut self, end: u64, slot: u64) {
+        self.set_data_len(self.data_len().max(end));
+        self.count_write(slot);
+    }
+
+    /// Updates the bookkeeping after the payload shrank to `payload_len` bytes in `slot`,
+    /// which counts as a write since the bytes past it are gone.
+    pub fn record_shrink(&mut self, payload_len: u64, slot: u64) {
+        self.set_data_len(self.data_len().min(payload_len));
+        self.count_write(slot);
+    }
+
+    fn count_write(&mut self, slot: u64) {
+        self.last_write_slot = slot.to_le_bytes();
+        self.write_count = self.write_count().wrapping_add(1).to_le_bytes();
+    }
+}
+
+/// Header of records created before the header grew the pending authority, the flags
+/// and the write bookkeeping, upgraded with [`crate::instruction::RecordInstruction::Migrate`]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct RecordDataV1 {
+    /// Struct version, always [`RecordDataV1::VERSION`]
+    pub version: u8,
+
+    /// The account allowed to update the data
+    pub authority: Pubkey,
+}
+
+impl RecordDataV1 {
+    /// Version of records using this header
+    pub const VERSION: u8 = 1;
+
+    /// Start of writable account data, right after the header
+    pub const WRITABLE_START_INDEX: usize = size_of::<Self>();
+}
+
+/// Authority requiring `m` of its `n` signers to sign in place of a single key
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct Multisig {
+    /// Struct version, [`Multisig::CURRENT_VERSION`] once initialized
+    pub version: u8,
+
+    /// Number of signers required
+    pub m: u8,
+
+    /// Number of valid signers
+    pub n: u8,
+
+    /// Signer public keys, only the first `n` are valid
+    pub signers: [Pubkey; Multisig::MAX_SIGNERS],
+}
+
+impl Multisig {
+    /// Version of initialized multisig accounts, kept apart from the record versions so
+    /// that neither kind of account can be taken for the other
+    pub const CURRENT_VERSION: u8 = 0x80;
+
+    /// Minimum number of multisig signers
+    pub const MIN_SIGNERS: usize = 1;
+
+    /// Maximum number of multisig signers
+    pub const MAX_SIGNERS: usize = 11;
+
+    /// Size of a multisig account
+    pub const LEN: usize = size_of::<Self>();
+
     pub fn is_initialized(&self) -> bool {
         self.version == Self::CURRENT_VERSION
     }
+
+    /// The valid signer public keys
+    pub fn signers(&self) -> &[Pubkey] {
+        &self.signers[..usize::from(self.n).min(Self::MAX_SIGNERS)]
+    }
+}
+
+/// Permission of a delegate to write within `[start, end)` of a record payload, stored at
+/// the address returned by [`crate::find_delegate_address`]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct DelegateData {
+    /// Struct version, [`DelegateData::CURRENT_VERSION`] once initialized
+    pub version: u8,
+
+    /// The record the permission applies to
+    pub record: Pubkey,
+
+    /// The account allowed to write
+    pub delegate: Pubkey,
+
+    /// The record authority that approved the permission, which lapses once the record
+    /// changes hands
+    pub authority: Pubkey,
+
+    /// [`RecordData::created_slot`] of the record when the permission was approved, which
+    /// lapses once the record is closed and created again
+    pub record_created_slot: [u8; 8],
+
+    /// First payload byte the delegate may write
+    pub start: [u8; 8],
+
+    /// End of the payload range the delegate may write, exclusive
+    pub end: [u8; 8],
+
+    /// Last slot in which the delegate may write, `0` when the permission never expires
+    pub expiry_slot: [u8; 8],
+}
+
+impl DelegateData {
+    /// Version of initialized delegate entries, kept apart from the record and multisig
+    /// versions
+    pub const CURRENT_VERSION: u8 = 0x81;
+
+    /// Size of a delegate entry account
+    pub const LEN: usize = size_of::<Self>();
+
+    /// Entry approved by `authority` letting `delegate` write `[start, end)` of the
+    /// `record` payload created in `record_created_slot` until `expiry_slot`
+    pub fn new(
+        record: &Pubkey,
+        delegate: &Pubkey,
+        authority: &Pubkey,
+        record_created_slot: u64,
+        start: u64,
+        end: u64,
+        expiry_slot: u64,
+    ) -> Self {
+        Self {
+            version: Self::CURRENT_VERSION,
+            record: *record,
+            delegate: *delegate,
+            authority: *authority,
+            record_created_slot: record_created_slot.to_le_bytes(),
+            start: start.to_le_bytes(),
+            end: end.to_le_bytes(),
+            expiry_slot: expiry_slot.to_le_bytes(),
+        }
+    }
+
+    pub fn is_initialized(&self) -> bool {
+        self.version == Self::CURRENT_VERSION
+    }
+
+    pub fn record_created_slot(&self) -> u64 {
+        u64::from_le_bytes(self.record_created_slot)
+    }
+
+    pub fn start(&self) -> u64 {
+        u64::from_le_bytes(self.start)
+    }
+
+    pub fn end(&self) -> u64 {
+        u64::from_le_bytes(self.end)
+    }
+
+    pub fn expiry_slot(&self) -> u64 {
+        u64::from_le_bytes(self.expiry_slot)
+    }
+
+    /// Whether the permission no longer holds in `slot`
+    pub fn is_expired(&self, slot: u64) -> bool {
+        self.expiry_slot() != 0 && slot > self.expiry_slot()
+    }
+
+    /// Whether the payload range `[start, end)` lies within the permitted range
+    pub fn covers(&self, start: u64, end: u64) -> bool {
+        self.start() <= start && end <= self.end()
+    }
 }
@@ -4,13 +4,19 @@ use solana_program::{
     rent::Rent,
 };
 use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
+use solana_system_interface::instruction as system_instruction;
 use {
-    pinocchio_sample::{instruction::RecordInstruction, state::RecordData},
+    pinocchio_sample::{
+        find_delegate_address, find_record_address,
+        instruction::{reallocate_steps, HashAlgorithm, RecordInstruction},
+        pinocchio::account_info::MAX_PERMITTED_DATA_INCREASE,
+        state::{DelegateData, Multisig, RecordData, RecordDataV1},
+    },
     solana_program_test::*,
     solana_sdk::{
+        account::Account,
         pubkey::Pubkey,
         signature::{Keypair, Signer},
-        system_instruction,
         transaction::Transaction,
     },
 };
@@ -28,6 +34,288 @@ fn instruction_initialize(record_account: &Pubkey, authority: &Pubkey) -> Instru
     }
 }
 
+fn instruction_initialize_checked(record_account: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: RecordInstruction::InitializeChecked.pack(),
+    }
+}
+
+fn instruction_initialize_with_data(
+    record_account: &Pubkey,
+    authority: &Pubkey,
+    data: &[u8],
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*authority, false),
+        ],
+        data: RecordInstruction::InitializeWithData { data }.pack(),
+    }
+}
+
+fn instruction_create_record(
+    record_account: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    data_length: u64,
+    seed: &[u8],
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, seed.is_empty()),
+            AccountMeta::new_readonly(*authority, !seed.is_empty()),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: RecordInstruction::CreateRecord { data_length, seed }.pack(),
+    }
+}
+
+fn instruction_create_record_pda(
+    record_account: &Pubkey,
+    authority: &Pubkey,
+    payer: &Pubkey,
+    data_length: u64,
+    seed: &[u8],
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: RecordInstruction::CreateRecordPda { data_length, seed }.pack(),
+    }
+}
+
+fn instruction_propose_authority(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    pending_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*pending_authority, false),
+        ],
+        data: RecordInstruction::ProposeAuthority.pack(),
+    }
+}
+
+fn instruction_accept_authority(record_account: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::AcceptAuthority.pack(),
+    }
+}
+
+fn instruction_cancel_proposal(record_account: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::CancelProposal.pack(),
+    }
+}
+
+fn instruction_freeze(record_account: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Freeze.pack(),
+    }
+}
+
+fn instruction_migrate(record_account: &Pubkey, signer: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Migrate { with_payer: false }.pack(),
+    }
+}
+
+fn instruction_migrate_with_payer(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: RecordInstruction::Migrate { with_payer: true }.pack(),
+    }
+}
+
+fn instruction_initialize_multisig(multisig: &Pubkey, signers: &[Pubkey], m: u8) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*multisig, false)];
+    accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(*signer, false)),
+    );
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts,
+        data: RecordInstruction::InitializeMultisig { m }.pack(),
+    }
+}
+
+/// Turns the authority signature of `instruction` into the signatures of the multisig
+/// `signers`, the authority account being at `authority_index`.
+fn with_multisig_signers(
+    mut instruction: Instruction,
+    authority_index: usize,
+    signers: &[Pubkey],
+) -> Instruction {
+    instruction.accounts[authority_index].is_signer = false;
+    instruction.accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(*signer, true)),
+    );
+    instruction
+}
+
+fn delegate_entry_address(record_account: &Pubkey, delegate: &Pubkey) -> Pubkey {
+    Pubkey::new_from_array(
+        find_delegate_address(
+            &record_account.to_bytes(),
+            &delegate.to_bytes(),
+            &CUSTOM_PROGRAM_ID.to_bytes(),
+        )
+        .0,
+    )
+}
+
+fn instruction_approve_delegate(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    delegate: &Pubkey,
+    payer: &Pubkey,
+    start: u64,
+    end: u64,
+    expiry_slot: u64,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(delegate_entry_address(record_account, delegate), false),
+            AccountMeta::new_readonly(*delegate, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: RecordInstruction::ApproveDelegate {
+            start,
+            end,
+            expiry_slot,
+        }
+        .pack(),
+    }
+}
+
+fn instruction_revoke_delegate(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    delegate: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(delegate_entry_address(record_account, delegate), false),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::RevokeDelegate.pack(),
+    }
+}
+
+fn instruction_write_as_delegate(
+    record_account: &Pubkey,
+    delegate: &Pubkey,
+    offset: u64,
+    data: &[u8],
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*delegate, true),
+            AccountMeta::new_readonly(delegate_entry_address(record_account, delegate), false),
+        ],
+        data: RecordInstruction::Write { offset, data }.pack(),
+    }
+}
+
+fn instruction_append(record_account: &Pubkey, signer: &Pubkey, data: &[u8]) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Append {
+            data,
+            with_payer: false,
+        }
+        .pack(),
+    }
+}
+
+fn instruction_append_with_payer(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    data: &[u8],
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: RecordInstruction::Append {
+            data,
+            with_payer: true,
+        }
+        .pack(),
+    }
+}
+
 fn instruction_write(
     record_account: &Pubkey,
     signer: &Pubkey,
@@ -44,42 +332,52 @@ fn instruction_write(
     }
 }
 
-fn instruction_set_authority(
+fn instruction_write_if_version(
     record_account: &Pubkey,
     signer: &Pubkey,
-    new_authority: &Pubkey,
+    expected_seq: u64,
+    offset: u64,
+    data: &[u8],
 ) -> Instruction {
     Instruction {
         program_id: CUSTOM_PROGRAM_ID,
         accounts: vec![
             AccountMeta::new(*record_account, false),
             AccountMeta::new_readonly(*signer, true),
-            AccountMeta::new_readonly(*new_authority, false),
         ],
-        data: RecordInstruction::SetAuthority.pack(),
+        data: RecordInstruction::WriteIfVersion {
+            expected_seq,
+            offset,
+            data,
+        }
+        .pack(),
     }
 }
 
-fn instruction_close_account(
+fn instruction_write_many(
     record_account: &Pubkey,
     signer: &Pubkey,
-    receiver: &Pubkey,
+    segments: &[(u32, &[u8])],
 ) -> Instruction {
     Instruction {
         program_id: CUSTOM_PROGRAM_ID,
         accounts: vec![
             AccountMeta::new(*record_account, false),
             AccountMeta::new_readonly(*signer, true),
-            AccountMeta::new(*receiver, false),
         ],
-        data: RecordInstruction::CloseAccount.pack(),
+        data: RecordInstruction::WriteMany {
+            segments: segments.to_vec(),
+        }
+        .pack(),
     }
 }
 
-fn instruction_reallocate(
+fn instruction_fill(
     record_account: &Pubkey,
     signer: &Pubkey,
-    data_length: u64,
+    offset: u64,
+    length: u64,
+    byte: u8,
 ) -> Instruction {
     Instruction {
         program_id: CUSTOM_PROGRAM_ID,
@@ -87,34 +385,2671 @@ fn instruction_reallocate(
             AccountMeta::new(*record_account, false),
             AccountMeta::new_readonly(*signer, true),
         ],
-        data: RecordInstruction::Reallocate { data_length }.pack(),
+        data: RecordInstruction::Fill {
+            offset,
+            length,
+            byte,
+        }
+        .pack(),
     }
 }
 
-async fn initialize_storage_account(
-    context: &mut ProgramTestContext,
-    authority: &Keypair,
-    account: &Keypair,
-    data: &[u8],
-) {
-    let account_length = std::mem::size_of::<RecordData>()
-        .checked_add(data.len())
-        .unwrap();
+fn instruction_copy_range(
+    source_account: &Pubkey,
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    src_offset: u64,
+    dst_offset: u64,
+    length: u64,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(*source_account, false),
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::CopyRange {
+            src_offset,
+            dst_offset,
+            length,
+        }
+        .pack(),
+    }
+}
+
+fn instruction_shift(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    offset: u64,
+    length: u64,
+    delta: i64,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Shift {
+            offset,
+            length,
+            delta,
+            with_payer: false,
+        }
+        .pack(),
+    }
+}
+
+fn instruction_shift_with_payer(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    offset: u64,
+    length: u64,
+    delta: i64,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: RecordInstruction::Shift {
+            offset,
+            length,
+            delta,
+            with_payer: true,
+        }
+        .pack(),
+    }
+}
+
+fn instruction_verify_hash(
+    record_account: &Pubkey,
+    offset: u64,
+    length: u64,
+    algorithm: HashAlgorithm,
+    expected: [u8; 32],
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![AccountMeta::new_readonly(*record_account, false)],
+        data: RecordInstruction::VerifyHash {
+            offset,
+            length,
+            algorithm,
+            expected,
+        }
+        .pack(),
+    }
+}
+
+fn instruction_read(record_account: &Pubkey, offset: u64, length: u64) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![AccountMeta::new_readonly(*record_account, false)],
+        data: RecordInstruction::Read { offset, length }.pack(),
+    }
+}
+
+fn instruction_set_authority(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new_readonly(*new_authority, false),
+        ],
+        data: RecordInstruction::SetAuthority.pack(),
+    }
+}
+
+fn instruction_close_account(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    receiver: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*receiver, false),
+        ],
+        data: RecordInstruction::CloseAccount.pack(),
+    }
+}
+
+fn instruction_reallocate(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    data_length: u64,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+        ],
+        data: RecordInstruction::Reallocate { data_length }.pack(),
+    }
+}
+
+fn instruction_reallocate_with_destination(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    destination: &Pubkey,
+    data_length: u64,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*destination, false),
+        ],
+        data: RecordInstruction::Reallocate { data_length }.pack(),
+    }
+}
+
+fn instruction_reallocate_with_payer(
+    record_account: &Pubkey,
+    signer: &Pubkey,
+    payer: &Pubkey,
+    data_length: u64,
+) -> Instruction {
+    Instruction {
+        program_id: CUSTOM_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new(*record_account, false),
+            AccountMeta::new_readonly(*signer, true),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+        ],
+        data: RecordInstruction::ReallocateWithPayer { data_length }.pack(),
+    }
+}
+
+async fn initialize_storage_account(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    account: &Keypair,
+    data: &[u8],
+) {
+    let account_length = std::mem::size_of::<RecordData>()
+        .checked_add(data.len())
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                1.max(Rent::default().minimum_balance(account_length)),
+                account_length as u64,
+                &CUSTOM_PROGRAM_ID,
+            ),
+            instruction_initialize(&account.pubkey(), &authority.pubkey()),
+            instruction_write(&account.pubkey(), &authority.pubkey(), 0, data),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, authority, account],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+}
+
+async fn create_multisig(
+    context: &mut ProgramTestContext,
+    multisig: &Keypair,
+    signers: &[Pubkey],
+    m: u8,
+) -> Result<(), BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &multisig.pubkey(),
+                Rent::default().minimum_balance(Multisig::LEN),
+                Multisig::LEN as u64,
+                &CUSTOM_PROGRAM_ID,
+            ),
+            instruction_initialize_multisig(&multisig.pubkey(), signers, m),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, multisig],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+fn add_foreign_record(program_test: &mut ProgramTest, authority: &Pubkey) -> Pubkey {
+    let record = Pubkey::new_unique();
+    let data_length = RecordData::WRITABLE_START_INDEX + 8;
+    let mut data = vec![0u8; data_length];
+    let account_data =
+        bytemuck::from_bytes_mut::<RecordData>(&mut data[..RecordData::WRITABLE_START_INDEX]);
+    account_data.version = RecordData::CURRENT_VERSION;
+    account_data.authority = authority.to_bytes();
+    program_test.add_account(
+        record,
+        Account {
+            lamports: Rent::default().minimum_balance(data_length),
+            data,
+            owner: Pubkey::new_unique(),
+            ..Account::default()
+        },
+    );
+    record
+}
+
+/// Adds a record stored with the version 1 header, holding `lamports`.
+fn add_v1_record(
+    program_test: &mut ProgramTest,
+    authority: &Pubkey,
+    payload: &[u8],
+    lamports: u64,
+) -> Pubkey {
+    let record = Pubkey::new_unique();
+    let mut data = vec![0u8; RecordDataV1::WRITABLE_START_INDEX];
+    let account_data = bytemuck::from_bytes_mut::<RecordDataV1>(&mut data);
+    account_data.version = RecordDataV1::VERSION;
+    account_data.authority = authority.to_bytes();
+    data.extend_from_slice(payload);
+    program_test.add_account(
+        record,
+        Account {
+            lamports,
+            data,
+            owner: CUSTOM_PROGRAM_ID,
+            ..Account::default()
+        },
+    );
+    record
+}
+
+#[tokio::test]
+async fn initialize_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[111u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let account_data =
+        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
+            .unwrap();
+    assert_eq!(
+        account_data.authority.as_slice(),
+        authority.pubkey().as_array()
+    );
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+    assert_eq!(&account.data[RecordData::WRITABLE_START_INDEX..], data);
+}
+
+#[tokio::test]
+async fn write_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let new_data = &[200u8; 8];
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_write(
+            &account.pubkey(),
+            &authority.pubkey(),
+            0,
+            new_data,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+
+    let account_data =
+        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
+            .unwrap();
+    assert_eq!(
+        account_data.authority.as_slice(),
+        authority.pubkey().as_array()
+    );
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+    assert_eq!(account_data.data_len(), new_data.len() as u64);
+    assert_eq!(account_data.write_count(), 2);
+    assert_eq!(&account.data[RecordData::WRITABLE_START_INDEX..], new_data);
+}
+
+#[tokio::test]
+async fn write_fail_wrong_authority() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let new_data = &[200u8; 8];
+    let wrong_authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_write(
+            &account.pubkey(),
+            &wrong_authority.pubkey(),
+            0,
+            new_data,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn close_account_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    let account_length = std::mem::size_of::<RecordData>()
+        .checked_add(data.len())
+        .unwrap();
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let recipient = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_close_account(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &recipient,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let record = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap();
+    let account = context
+        .banks_client
+        .get_account(recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        account.lamports,
+        1.max(Rent::default().minimum_balance(account_length))
+    );
+    assert!(record.is_none());
+}
+
+#[tokio::test]
+async fn close_account_fail_reinitialize_same_transaction() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    let account_length = std::mem::size_of::<RecordData>()
+        .checked_add(data.len())
+        .unwrap();
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let recipient = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_close_account(&account.pubkey(), &authority.pubkey(), &recipient),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                Rent::default().minimum_balance(account_length),
+            ),
+            instruction_initialize(&account.pubkey(), &authority.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            2,
+            InstructionError::Custom(RecordError::IncorrectOwner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn set_authority_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_set_authority(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = bytemuck::try_from_bytes::<RecordData>(
+        &account_handle.data[..RecordData::WRITABLE_START_INDEX],
+    )
+    .unwrap();
+    assert_eq!(
+        account_data.authority.as_slice(),
+        new_authority.pubkey().as_array()
+    );
+
+    let new_data = &[200u8; 8];
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_write(
+            &account.pubkey(),
+            &new_authority.pubkey(),
+            0,
+            new_data,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data = bytemuck::try_from_bytes::<RecordData>(
+        &account_handle.data[..RecordData::WRITABLE_START_INDEX],
+    )
+    .unwrap();
+    assert_eq!(
+        account_data.authority.as_slice(),
+        new_authority.pubkey().as_array()
+    );
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+        new_data,
+    );
+}
+
+#[tokio::test]
+async fn reallocate_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let new_data_length = 16u64;
+    let expected_account_data_length = RecordData::WRITABLE_START_INDEX
+        .checked_add(new_data_length as usize)
+        .unwrap();
+
+    let delta_account_data_length = new_data_length.saturating_sub(data.len() as u64);
+    let additional_lamports_needed =
+        Rent::default().minimum_balance(delta_account_data_length as usize);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_reallocate(&account.pubkey(), &authority.pubkey(), new_data_length),
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                additional_lamports_needed,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(account_handle.data.len(), expected_account_data_length);
+}
+
+#[tokio::test]
+async fn reallocate_with_payer_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let new_data_length = 1024u64;
+    let expected_account_data_length = RecordData::WRITABLE_START_INDEX
+        .checked_add(new_data_length as usize)
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_reallocate_with_payer(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            new_data_length,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account_handle.data.len(), expected_account_data_length);
+    assert_eq!(
+        account_handle.lamports,
+        Rent::default().minimum_balance(expected_account_data_length)
+    );
+}
+
+#[tokio::test]
+async fn reallocate_beyond_increase_limit_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let new_data_length = 3 * MAX_PERMITTED_DATA_INCREASE as u64 + 8;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_reallocate_with_payer(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            new_data_length,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    // the reached length survives the rent transfer made on behalf of the payer
+    let return_data = context
+        .banks_client
+        .simulate_transaction(transaction.clone())
+        .await
+        .unwrap()
+        .simulation_details
+        .unwrap()
+        .return_data
+        .unwrap();
+    assert_eq!(
+        return_data.data,
+        ((data.len() + MAX_PERMITTED_DATA_INCREASE) as u64).to_le_bytes()
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let current_data_length = data.len() + MAX_PERMITTED_DATA_INCREASE;
+    assert_eq!(
+        account_handle.data.len(),
+        RecordData::WRITABLE_START_INDEX + current_data_length
+    );
+
+    let steps = reallocate_steps(current_data_length as u64, new_data_length, true);
+    assert_eq!(steps.len(), 2);
+    let instructions = steps
+        .iter()
+        .map(|step| Instruction {
+            program_id: CUSTOM_PROGRAM_ID,
+            accounts: vec![
+                AccountMeta::new(account.pubkey(), false),
+                AccountMeta::new_readonly(authority.pubkey(), true),
+                AccountMeta::new(context.payer.pubkey(), true),
+                AccountMeta::new_readonly(solana_system_interface::program::ID, false),
+            ],
+            data: step.pack(),
+        })
+        .collect::<Vec<_>>();
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let expected_account_data_length = RecordData::WRITABLE_START_INDEX
+        .checked_add(new_data_length as usize)
+        .unwrap();
+    assert_eq!(account_handle.data.len(), expected_account_data_length);
+    assert_eq!(
+        account_handle.lamports,
+        Rent::default().minimum_balance(expected_account_data_length)
+    );
+}
+
+async fn reallocate_shrink(new_data_length: u64) {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    let account_length = std::mem::size_of::<RecordData>()
+        .checked_add(data.len())
+        .unwrap();
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let expected_account_data_length = RecordData::WRITABLE_START_INDEX
+        .checked_add(new_data_length as usize)
+        .unwrap();
+    let recipient = Pubkey::new_unique();
+    let recipient_starting_lamports = Rent::default().minimum_balance(0);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(
+                &context.payer.pubkey(),
+                &recipient,
+                recipient_starting_lamports,
+            ),
+            instruction_reallocate_with_destination(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &recipient,
+                new_data_length,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account_handle.data.len(), expected_account_data_length);
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
+        &data[..new_data_length as usize]
+    );
+    let minimum_balance = Rent::default().minimum_balance(expected_account_data_length);
+    assert_eq!(account_handle.lamports, minimum_balance);
+    let account_data = bytemuck::from_bytes::<RecordData>(
+        &account_handle.data[..RecordData::WRITABLE_START_INDEX],
+    );
+    assert_eq!(account_data.data_len(), new_data_length);
+    assert_eq!(account_data.write_count(), 2);
+
+    let recipient_handle = context
+        .banks_client
+        .get_account(recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        recipient_handle.lamports,
+        recipient_starting_lamports + 1.max(Rent::default().minimum_balance(account_length))
+            - minimum_balance
+    );
+}
+
+#[tokio::test]
+async fn reallocate_shrink_success() {
+    reallocate_shrink(4).await;
+}
+
+#[tokio::test]
+async fn reallocate_shrink_to_header_success() {
+    reallocate_shrink(0).await;
+}
+
+#[tokio::test]
+async fn reallocate_shrink_fail_missing_destination() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_reallocate(
+            &account.pubkey(),
+            &authority.pubkey(),
+            4,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+    );
+}
+
+#[tokio::test]
+async fn foreign_owner_fail() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let authority = Keypair::new();
+    let record = add_foreign_record(&mut program_test, &authority.pubkey());
+    let context: ProgramTestContext = program_test.start_with_context().await;
+
+    let recipient = Pubkey::new_unique();
+    let cases = [
+        (
+            instruction_initialize(&record, &authority.pubkey()),
+            vec![&context.payer],
+        ),
+        (
+            instruction_write(&record, &authority.pubkey(), 0, &[1u8; 8]),
+            vec![&context.payer, &authority],
+        ),
+        (
+            instruction_set_authority(&record, &authority.pubkey(), &recipient),
+            vec![&context.payer, &authority],
+        ),
+        (
+            instruction_close_account(&record, &authority.pubkey(), &recipient),
+            vec![&context.payer, &authority],
+        ),
+        (
+            instruction_reallocate(&record, &authority.pubkey(), 16),
+            vec![&context.payer, &authority],
+        ),
+    ];
+    for (instruction, signers) in cases {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &signers,
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::IncorrectOwner as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn write_fail_readonly_record() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let mut instruction = instruction_write(&account.pubkey(), &authority.pubkey(), 0, &[1u8; 8]);
+    instruction.accounts[0].is_writable = false;
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::AccountNotWritable as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn duplicate_accounts_fail() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let record = account.pubkey();
+
+    let cases = [
+        (
+            instruction_initialize(&record, &record),
+            vec![&context.payer],
+            InstructionError::AccountAlreadyInitialized,
+        ),
+        (
+            instruction_write(&record, &record, 0, &[1u8; 8]),
+            vec![&context.payer, &account],
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32),
+        ),
+        (
+            instruction_set_authority(&record, &record, &record),
+            vec![&context.payer, &account],
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32),
+        ),
+        (
+            instruction_close_account(&record, &authority.pubkey(), &record),
+            vec![&context.payer, &authority],
+            InstructionError::AccountBorrowFailed,
+        ),
+        (
+            instruction_reallocate_with_destination(&record, &authority.pubkey(), &record, 4),
+            vec![&context.payer, &authority],
+            InstructionError::AccountBorrowFailed,
+        ),
+        (
+            instruction_reallocate_with_payer(&record, &authority.pubkey(), &record, 16),
+            vec![&context.payer, &authority, &account],
+            InstructionError::InvalidArgument,
+        ),
+        (
+            instruction_reallocate(&record, &authority.pubkey(), u64::MAX),
+            vec![&context.payer, &authority],
+            InstructionError::Custom(RecordError::Overflow as u32),
+        ),
+    ];
+    for (instruction, signers, error) in cases {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &signers,
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, error)
+        );
+    }
+}
+
+async fn initialize_checked(
+    context: &mut ProgramTestContext,
+    lamports: u64,
+    authority_is_signer: bool,
+) -> Result<(), BanksClientError> {
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let account_length = std::mem::size_of::<RecordData>();
+
+    let mut instruction = instruction_initialize_checked(&account.pubkey(), &authority.pubkey());
+    let mut signers = vec![&context.payer, &account];
+    if authority_is_signer {
+        signers.push(&authority);
+    } else {
+        instruction.accounts[1].is_signer = false;
+    }
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                lamports,
+                account_length as u64,
+                &CUSTOM_PROGRAM_ID,
+            ),
+            instruction,
+        ],
+        Some(&context.payer.pubkey()),
+        &signers,
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await?;
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data =
+        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
+            .unwrap();
+    assert_eq!(
+        account_data.authority.as_slice(),
+        authority.pubkey().as_array()
+    );
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+    Ok(())
+}
+
+#[tokio::test]
+async fn initialize_checked_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let lamports = Rent::default().minimum_balance(std::mem::size_of::<RecordData>());
+    initialize_checked(&mut context, lamports, true)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn initialize_checked_fail_missing_signature() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let lamports = Rent::default().minimum_balance(std::mem::size_of::<RecordData>());
+    assert_eq!(
+        initialize_checked(&mut context, lamports, false)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn initialize_checked_fail_not_rent_exempt() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let lamports = Rent::default().minimum_balance(std::mem::size_of::<RecordData>()) - 1;
+    assert_eq!(
+        initialize_checked(&mut context, lamports, true)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::AccountNotRentExempt)
+    );
+}
+
+async fn initialize_with_data(
+    context: &mut ProgramTestContext,
+    authority: &Keypair,
+    account: &Keypair,
+    data_length: usize,
+    data: &[u8],
+) -> Result<(), BanksClientError> {
+    let account_length = std::mem::size_of::<RecordData>()
+        .checked_add(data_length)
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                Rent::default().minimum_balance(account_length),
+                account_length as u64,
+                &CUSTOM_PROGRAM_ID,
+            ),
+            instruction_initialize_with_data(&account.pubkey(), &authority.pubkey(), data),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, account],
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn initialize_with_data_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[111u8; 8];
+    initialize_with_data(&mut context, &authority, &account, 16, data)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data =
+        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
+            .unwrap();
+    assert_eq!(
+        account_data.authority.as_slice(),
+        authority.pubkey().as_array()
+    );
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+    assert_eq!(account_data.data_len(), data.len() as u64);
+    assert_eq!(account_data.write_count(), 1);
+    assert_eq!(
+        &account.data[RecordData::WRITABLE_START_INDEX..RecordData::WRITABLE_START_INDEX + 8],
+        data
+    );
+    assert_eq!(
+        &account.data[RecordData::WRITABLE_START_INDEX + 8..],
+        &[0u8; 8]
+    );
+}
+
+#[tokio::test]
+async fn initialize_with_data_fail_too_small() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    assert_eq!(
+        initialize_with_data(&mut context, &authority, &account, 4, &[111u8; 8])
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(1, InstructionError::AccountDataTooSmall)
+    );
+}
+
+async fn assert_created_record(
+    context: &mut ProgramTestContext,
+    record: &Pubkey,
+    authority: &Pubkey,
+    data_length: usize,
+) {
+    let account = context
+        .banks_client
+        .get_account(*record)
+        .await
+        .unwrap()
+        .unwrap();
+    let account_length = RecordData::WRITABLE_START_INDEX + data_length;
+    assert_eq!(account.owner, CUSTOM_PROGRAM_ID);
+    assert_eq!(account.data.len(), account_length);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account_length)
+    );
+    let account_data =
+        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
+            .unwrap();
+    assert_eq!(account_data.authority.as_slice(), authority.as_array());
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+}
+
+#[tokio::test]
+async fn create_record_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_create_record(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            64,
+            &[],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &account],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_created_record(&mut context, &account.pubkey(), &authority.pubkey(), 64).await;
+}
+
+#[tokio::test]
+async fn create_record_with_seed_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let seed = "record-1";
+    let record = Pubkey::create_with_seed(&authority.pubkey(), seed, &CUSTOM_PROGRAM_ID).unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_create_record(
+            &record,
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            64,
+            seed.as_bytes(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_created_record(&mut context, &record, &authority.pubkey(), 64).await;
+}
+
+#[tokio::test]
+async fn create_record_fail_seed_too_long() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_create_record(
+            &Pubkey::new_unique(),
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            64,
+            &[b'a'; 33],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MaxSeedLengthExceeded)
+    );
+}
+
+#[tokio::test]
+async fn create_record_pda_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let seed = b"domain-id-42";
+    let record = Pubkey::new_from_array(
+        find_record_address(
+            &authority.pubkey().to_bytes(),
+            seed,
+            &CUSTOM_PROGRAM_ID.to_bytes(),
+        )
+        .0,
+    );
+    // lamports sent ahead of time must not block the creation
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::transfer(&context.payer.pubkey(), &record, 1),
+            instruction_create_record_pda(
+                &record,
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                64,
+                seed,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_created_record(&mut context, &record, &authority.pubkey(), 64).await;
+}
+
+#[tokio::test]
+async fn create_record_pda_fail_wrong_address() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let record = Pubkey::new_from_array(
+        find_record_address(
+            &authority.pubkey().to_bytes(),
+            b"seed-a",
+            &CUSTOM_PROGRAM_ID.to_bytes(),
+        )
+        .0,
+    );
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_create_record_pda(
+            &record,
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+            64,
+            b"seed-b",
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidSeeds)
+    );
+}
+
+async fn get_record_header(context: &mut ProgramTestContext, record: &Pubkey) -> RecordData {
+    let account = context
+        .banks_client
+        .get_account(*record)
+        .await
+        .unwrap()
+        .unwrap();
+    *bytemuck::from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
+}
+
+#[tokio::test]
+async fn propose_accept_authority_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_propose_authority(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
+    assert_eq!(
+        account_data.authority.as_slice(),
+        authority.pubkey().as_array()
+    );
+    assert_eq!(
+        account_data.pending_authority.as_slice(),
+        new_authority.pubkey().as_array()
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_accept_authority(
+            &account.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
+    assert_eq!(
+        account_data.authority.as_slice(),
+        new_authority.pubkey().as_array()
+    );
+    assert!(!account_data.has_pending_authority());
+}
+
+#[tokio::test]
+async fn accept_authority_fail_wrong_signer() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+    let wrong_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_propose_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            ),
+            instruction_accept_authority(&account.pubkey(), &wrong_authority.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn cancel_proposal_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+    let new_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_propose_authority(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &new_authority.pubkey(),
+            ),
+            instruction_cancel_proposal(&account.pubkey(), &authority.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
+    assert!(!account_data.has_pending_authority());
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_accept_authority(
+            &account.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::NoPendingAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn v1_record_write_set_authority_and_close_success() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let authority = Keypair::new();
+    let payload = [111u8; 8];
+    let lamports = Rent::default().minimum_balance(RecordDataV1::WRITABLE_START_INDEX + 8);
+    let record = add_v1_record(&mut program_test, &authority.pubkey(), &payload, lamports);
+    let context: ProgramTestContext = program_test.start_with_context().await;
+    let new_authority = Keypair::new();
+    let new_data = [222u8; 4];
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_write(&record, &authority.pubkey(), 4, &new_data),
+            instruction_set_authority(&record, &authority.pubkey(), &new_authority.pubkey()),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(record)
+        .await
+        .unwrap()
+        .unwrap();
+    let account_data =
+        bytemuck::from_bytes::<RecordDataV1>(&account.data[..RecordDataV1::WRITABLE_START_INDEX]);
+    assert_eq!(account_data.version, RecordDataV1::VERSION);
+    assert_eq!(
+        account_data.authority.as_slice(),
+        new_authority.pubkey().as_array()
+    );
+    assert_eq!(
+        &account.data[RecordDataV1::WRITABLE_START_INDEX..],
+        &[111, 111, 111, 111, 222, 222, 222, 222]
+    );
+
+    let recipient = Pubkey::new_unique();
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_close_account(
+            &record,
+            &new_authority.pubkey(),
+            &recipient,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &new_authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert!(context
+        .banks_client
+        .get_account(record)
+        .await
+        .unwrap()
+        .is_none());
+    let account = context
+        .banks_client
+        .get_account(recipient)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.lamports, lamports);
+}
+
+#[tokio::test]
+async fn propose_authority_fail_v1_record() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let authority = Keypair::new();
+    let payload = [111u8; 8];
+    let lamports = Rent::default().minimum_balance(RecordDataV1::WRITABLE_START_INDEX + 8);
+    let record = add_v1_record(&mut program_test, &authority.pubkey(), &payload, lamports);
+    let context: ProgramTestContext = program_test.start_with_context().await;
+    let new_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_propose_authority(
+            &record,
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::OutdatedVersion as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn freeze_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    let data = &[222u8; 8];
+    initialize_storage_account(&mut context, &authority, &account, data).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_freeze(&account.pubkey(), &authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
+    assert!(account_data.is_initialized());
+    assert!(account_data.is_frozen());
+
+    let record = account.pubkey();
+    let recipient = Pubkey::new_unique();
+    let instructions = [
+        instruction_write(&record, &authority.pubkey(), 0, &[1u8; 8]),
+        instruction_set_authority(&record, &authority.pubkey(), &recipient),
+        instruction_close_account(&record, &authority.pubkey(), &recipient),
+        instruction_reallocate(&record, &authority.pubkey(), 16),
+        instruction_freeze(&record, &authority.pubkey()),
+        instruction_append(&record, &authority.pubkey(), &[1u8]),
+        instruction_write_many(&record, &authority.pubkey(), &[(0, &[1u8; 2])]),
+        instruction_fill(&record, &authority.pubkey(), 0, 4, 1),
+        instruction_copy_range(&record, &record, &authority.pubkey(), 0, 4, 4),
+        instruction_shift(&record, &authority.pubkey(), 0, 4, 4),
+        instruction_approve_delegate(
+            &record,
+            &authority.pubkey(),
+            &recipient,
+            &context.payer.pubkey(),
+            0,
+            8,
+            0,
+        ),
+    ];
+    for instruction in instructions {
+        let transaction = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&context.payer.pubkey()),
+            &[&context.payer, &authority],
+            context.last_blockhash,
+        );
+        assert_eq!(
+            context
+                .banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(RecordError::Frozen as u32)
+            )
+        );
+    }
+}
+
+#[tokio::test]
+async fn migrate_success() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let authority = Keypair::new();
+    let payload = [111u8; 8];
+    let account_length = RecordData::WRITABLE_START_INDEX + payload.len();
+    let record = add_v1_record(
+        &mut program_test,
+        &authority.pubkey(),
+        &payload,
+        Rent::default().minimum_balance(account_length),
+    );
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_freeze(&record, &authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::OutdatedVersion as u32)
+        )
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_migrate(&record, &authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(record)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), account_length);
+    assert_eq!(&account.data[RecordData::WRITABLE_START_INDEX..], &payload);
+
+    let account_data = get_record_header(&mut context, &record).await;
+    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
+    assert_eq!(
+        account_data.authority.as_slice(),
+        authority.pubkey().as_array()
+    );
+    assert_eq!(account_data.data_len(), payload.len() as u64);
+    assert_eq!(account_data.write_count(), 0);
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_migrate(&record, &authority.pubkey()),
+            instruction_write(&record, &authority.pubkey(), 0, &[1u8; 8]),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_data = get_record_header(&mut context, &record).await;
+    assert_eq!(account_data.write_count(), 1);
+}
+
+#[tokio::test]
+async fn migrate_with_payer_success() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let authority = Keypair::new();
+    let payload = [111u8; 8];
+    let record = add_v1_record(
+        &mut program_test,
+        &authority.pubkey(),
+        &payload,
+        Rent::default().minimum_balance(RecordDataV1::WRITABLE_START_INDEX + payload.len()),
+    );
+    let context: ProgramTestContext = program_test.start_with_context().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_migrate_with_payer(
+            &record,
+            &authority.pubkey(),
+            &context.payer.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(record)
+        .await
+        .unwrap()
+        .unwrap();
+    let account_length = RecordData::WRITABLE_START_INDEX + payload.len();
+    assert_eq!(account.data.len(), account_length);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account_length)
+    );
+    assert_eq!(&account.data[RecordData::WRITABLE_START_INDEX..], &payload);
+}
+
+#[tokio::test]
+async fn migrate_fail_wrong_authority() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let authority = Keypair::new();
+    let payload = [111u8; 8];
+    let record = add_v1_record(
+        &mut program_test,
+        &authority.pubkey(),
+        &payload,
+        Rent::default().minimum_balance(RecordData::WRITABLE_START_INDEX + payload.len()),
+    );
+    let context: ProgramTestContext = program_test.start_with_context().await;
+    let wrong_authority = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_migrate(&record, &wrong_authority.pubkey())],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &wrong_authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+/// Creates a record controlled by a 2 of 3 multisig and writes to it, signed by the
+/// multisig signers at `signer_indexes`.
+async fn multisig_write(signer_indexes: &[usize]) -> Result<(), BanksClientError> {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    let multisig = Keypair::new();
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let signer_keys = signers.iter().map(Keypair::pubkey).collect::<Vec<_>>();
+    create_multisig(&mut context, &multisig, &signer_keys, 2)
+        .await
+        .unwrap();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_set_authority(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &multisig.pubkey(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let signing_keys = signer_indexes
+        .iter()
+        .map(|&index| signer_keys[index])
+        .collect::<Vec<_>>();
+    let mut keypairs = vec![&context.payer];
+    keypairs.extend(signer_indexes.iter().map(|&index| &signers[index]));
+    let transaction = Transaction::new_signed_with_payer(
+        &[with_multisig_signers(
+            instruction_write(&account.pubkey(), &multisig.pubkey(), 0, &[111u8; 8]),
+            1,
+            &signing_keys,
+        )],
+        Some(&context.payer.pubkey()),
+        &keypairs,
+        context.last_blockhash,
+    );
+    context.banks_client.process_transaction(transaction).await
+}
+
+#[tokio::test]
+async fn multisig_write_success() {
+    multisig_write(&[0, 2]).await.unwrap();
+}
+
+#[tokio::test]
+async fn multisig_write_fail_missing_signer() {
+    assert_eq!(
+        multisig_write(&[1]).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+    );
+}
+
+#[tokio::test]
+async fn multisig_initialize_checked_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let multisig = Keypair::new();
+    let signers = [Keypair::new(), Keypair::new()];
+    let signer_keys = signers.iter().map(Keypair::pubkey).collect::<Vec<_>>();
+    create_multisig(&mut context, &multisig, &signer_keys, 2)
+        .await
+        .unwrap();
+
+    let account = Keypair::new();
+    let account_length = RecordData::WRITABLE_START_INDEX + 8;
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            system_instruction::create_account(
+                &context.payer.pubkey(),
+                &account.pubkey(),
+                Rent::default().minimum_balance(account_length),
+                account_length as u64,
+                &CUSTOM_PROGRAM_ID,
+            ),
+            with_multisig_signers(
+                instruction_initialize_checked(&account.pubkey(), &multisig.pubkey()),
+                1,
+                &signer_keys,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &account, &signers[0], &signers[1]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
+    assert_eq!(account_data.authority, multisig.pubkey().to_bytes());
+}
+
+#[tokio::test]
+async fn initialize_multisig_fail_too_many_required() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let multisig = Keypair::new();
+    let signer_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+    assert_eq!(
+        create_multisig(&mut context, &multisig, &signer_keys, 3)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            1,
+            InstructionError::Custom(RecordError::InvalidNumberOfRequiredSigners as u32)
+        )
+    );
+}
+
+/// Creates a record holding eight bytes and lets a new delegate write `[4, 8)` of it until
+/// `expiry_slot`, returning the record, its authority and the delegate.
+async fn create_delegated_record(
+    context: &mut ProgramTestContext,
+    expiry_slot: u64,
+) -> (Pubkey, Keypair, Keypair) {
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(context, &authority, &account, &[222u8; 8]).await;
+    let delegate = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_approve_delegate(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &delegate.pubkey(),
+            &context.payer.pubkey(),
+            4,
+            8,
+            expiry_slot,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    (account.pubkey(), authority, delegate)
+}
+
+/// Writes `data` at `offset` of the record as the delegate, returning the payload.
+async fn delegate_write(
+    context: &mut ProgramTestContext,
+    record: &Pubkey,
+    delegate: &Keypair,
+    offset: u64,
+    data: &[u8],
+) -> Result<Vec<u8>, BanksClientError> {
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_write_as_delegate(
+            record,
+            &delegate.pubkey(),
+            offset,
+            data,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, delegate],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await?;
+
+    let account = context
+        .banks_client
+        .get_account(*record)
+        .await
+        .unwrap()
+        .unwrap();
+    Ok(account.data[RecordData::WRITABLE_START_INDEX..].to_vec())
+}
+
+#[tokio::test]
+async fn delegate_write_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+    let (record, _, delegate) = create_delegated_record(&mut context, 0).await;
+
+    assert_eq!(
+        delegate_write(&mut context, &record, &delegate, 4, &[111u8; 4])
+            .await
+            .unwrap(),
+        [222, 222, 222, 222, 111, 111, 111, 111]
+    );
+}
+
+#[tokio::test]
+async fn delegate_write_fail_out_of_range() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+    let (record, _, delegate) = create_delegated_record(&mut context, 0).await;
+
+    assert_eq!(
+        delegate_write(&mut context, &record, &delegate, 2, &[111u8; 4])
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::DelegateOutOfRange as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn delegate_write_fail_expired() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    let (record, _, delegate) = create_delegated_record(&mut context, slot + 10).await;
+
+    context.warp_to_slot(slot + 11).unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    assert_eq!(
+        delegate_write(&mut context, &record, &delegate, 4, &[111u8; 4])
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::DelegateExpired as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn delegate_write_fail_authority_changed() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+    let (record, authority, delegate) = create_delegated_record(&mut context, 0).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_set_authority(
+            &record,
+            &authority.pubkey(),
+            &Pubkey::new_unique(),
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        delegate_write(&mut context, &record, &delegate, 4, &[111u8; 4])
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn delegate_write_fail_record_recreated() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+    let delegate = Keypair::new();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_approve_delegate(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &delegate.pubkey(),
+                &context.payer.pubkey(),
+                0,
+                8,
+                0,
+            ),
+            instruction_close_account(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &Pubkey::new_unique(),
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    // the same authority creates the record again at the same address in a later slot
+    let slot = context.banks_client.get_root_slot().await.unwrap();
+    context.warp_to_slot(slot + 2).unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    assert_eq!(
+        delegate_write(&mut context, &account.pubkey(), &delegate, 0, &[111u8; 4])
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectAuthority as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn approve_delegate_fail_v1_record() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let authority = Keypair::new();
+    let record = add_v1_record(
+        &mut program_test,
+        &authority.pubkey(),
+        &[222u8; 8],
+        Rent::default().minimum_balance(RecordDataV1::WRITABLE_START_INDEX + 8),
+    );
+    let context: ProgramTestContext = program_test.start_with_context().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_approve_delegate(
+            &record,
+            &authority.pubkey(),
+            &Pubkey::new_unique(),
+            &context.payer.pubkey(),
+            0,
+            8,
+            0,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::OutdatedVersion as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn revoke_delegate_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+    let delegate = Keypair::new();
+    let receiver = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_approve_delegate(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &delegate.pubkey(),
+                &context.payer.pubkey(),
+                0,
+                8,
+                0,
+            ),
+            instruction_revoke_delegate(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &delegate.pubkey(),
+                &receiver,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(receiver).await.unwrap(),
+        Rent::default().minimum_balance(DelegateData::LEN)
+    );
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_write_as_delegate(
+            &account.pubkey(),
+            &delegate.pubkey(),
+            0,
+            &[111u8; 8],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &delegate],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::IncorrectOwner as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn revoke_delegate_closed_record_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+    let (record, authority, delegate) = create_delegated_record(&mut context, 0).await;
+    let receiver = Pubkey::new_unique();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_close_account(&record, &authority.pubkey(), &Pubkey::new_unique()),
+            instruction_revoke_delegate(
+                &record,
+                &authority.pubkey(),
+                &delegate.pubkey(),
+                &receiver,
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(receiver).await.unwrap(),
+        Rent::default().minimum_balance(DelegateData::LEN)
+    );
+}
+
+#[tokio::test]
+async fn append_with_payer_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[
+            instruction_append_with_payer(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &[1u8; 4],
+            ),
+            instruction_append_with_payer(
+                &account.pubkey(),
+                &authority.pubkey(),
+                &context.payer.pubkey(),
+                &[2u8; 4],
+            ),
+        ],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let account_handle = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let expected_account_data_length = RecordData::WRITABLE_START_INDEX + 16;
+    assert_eq!(account_handle.data.len(), expected_account_data_length);
+    assert_eq!(
+        account_handle.lamports,
+        Rent::default().minimum_balance(expected_account_data_length)
+    );
+    assert_eq!(
+        &account_handle.data[RecordData::WRITABLE_START_INDEX + 8..],
+        &[1, 1, 1, 1, 2, 2, 2, 2]
+    );
+
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
+    assert_eq!(account_data.data_len(), 16);
+}
+
+#[tokio::test]
+async fn append_fail_full() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_append(
+            &account.pubkey(),
+            &authority.pubkey(),
+            &[1u8; 4],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::RecordFull as u32)
+        )
+    );
+}
+
+/// Writes to a record written once before, expecting the write sequence `expected_seq`.
+async fn write_if_version(expected_seq: u64) -> Result<(), BanksClientError> {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_write_if_version(
+            &account.pubkey(),
+            &authority.pubkey(),
+            expected_seq,
+            0,
+            &[111u8; 8],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await?;
+
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
+    assert_eq!(account_data.write_count(), expected_seq + 1);
+    Ok(())
+}
+
+#[tokio::test]
+async fn write_if_version_success() {
+    write_if_version(1).await.unwrap();
+}
+
+#[tokio::test]
+async fn write_if_version_fail_conflict() {
+    assert_eq!(
+        write_if_version(0).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::WriteConflict as u32)
+        )
+    );
+}
+
+/// Writes `segments` to a record holding eight `222` bytes and returns its payload.
+async fn write_many(segments: &[(u32, &[u8])]) -> Result<Vec<u8>, BanksClientError> {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_write_many(
+            &account.pubkey(),
+            &authority.pubkey(),
+            segments,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await?;
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    Ok(account.data[RecordData::WRITABLE_START_INDEX..].to_vec())
+}
+
+#[tokio::test]
+async fn write_many_success() {
+    assert_eq!(
+        write_many(&[(0, &[1, 1]), (5, &[2, 2, 2])]).await.unwrap(),
+        [1, 1, 222, 222, 222, 2, 2, 2]
+    );
+}
+
+#[tokio::test]
+async fn write_many_fail_segment_too_large() {
+    assert_eq!(
+        write_many(&[(0, &[1, 1]), (7, &[2, 2])])
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall)
+    );
+}
+
+#[tokio::test]
+async fn write_many_success_empty_segment() {
+    assert_eq!(
+        write_many(&[(0, &[1, 1, 1]), (1, &[])]).await.unwrap(),
+        [1, 1, 1, 222, 222, 222, 222, 222]
+    );
+}
+
+#[tokio::test]
+async fn write_many_fail_overlapping_segments() {
+    assert_eq!(
+        write_many(&[(0, &[1, 1, 1]), (2, &[2, 2])])
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+    );
+}
+
+/// Fills a range of a record holding eight `222` bytes and returns its payload.
+async fn fill(offset: u64, length: u64) -> Result<Vec<u8>, BanksClientError> {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_fill(
+            &account.pubkey(),
+            &authority.pubkey(),
+            offset,
+            length,
+            0,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await?;
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    Ok(account.data[RecordData::WRITABLE_START_INDEX..].to_vec())
+}
+
+#[tokio::test]
+async fn fill_success() {
+    assert_eq!(fill(2, 4).await.unwrap(), [222, 222, 0, 0, 0, 0, 222, 222]);
+}
+
+#[tokio::test]
+async fn fill_fail_beyond_payload() {
+    assert_eq!(
+        fill(4, 5).await.unwrap_err().unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall)
+    );
+}
+
+#[tokio::test]
+async fn copy_range_success() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let source_authority = Keypair::new();
+    let source = Keypair::new();
+    initialize_storage_account(
+        &mut context,
+        &source_authority,
+        &source,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    )
+    .await;
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
 
     let transaction = Transaction::new_signed_with_payer(
-        &[
-            system_instruction::create_account(
-                &context.payer.pubkey(),
-                &account.pubkey(),
-                1.max(Rent::default().minimum_balance(account_length)),
-                account_length as u64,
-                &CUSTOM_PROGRAM_ID,
-            ),
-            instruction_initialize(&account.pubkey(), &authority.pubkey()),
-            instruction_write(&account.pubkey(), &authority.pubkey(), 0, data),
-        ],
+        &[instruction_copy_range(
+            &source.pubkey(),
+            &account.pubkey(),
+            &authority.pubkey(),
+            2,
+            4,
+            4,
+        )],
         Some(&context.payer.pubkey()),
-        &[&context.payer, authority, account],
+        &[&context.payer, &authority],
         context.last_blockhash,
     );
     context
@@ -122,17 +3057,52 @@ async fn initialize_storage_account(
         .process_transaction(transaction)
         .await
         .unwrap();
+
+    let account = context
+        .banks_client
+        .get_account(account.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        &account.data[RecordData::WRITABLE_START_INDEX..],
+        &[222, 222, 222, 222, 3, 4, 5, 6]
+    );
 }
 
 #[tokio::test]
-async fn initialize_success() {
-    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+async fn copy_range_from_v1_record_success() {
+    let mut program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let source = add_v1_record(
+        &mut program_test,
+        &Pubkey::new_unique(),
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+        Rent::default().minimum_balance(RecordDataV1::WRITABLE_START_INDEX + 8),
+    );
     let mut context: ProgramTestContext = program_test.start_with_context().await;
 
     let authority = Keypair::new();
     let account = Keypair::new();
-    let data = &[111u8; 8];
-    initialize_storage_account(&mut context, &authority, &account, data).await;
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[instruction_copy_range(
+            &source,
+            &account.pubkey(),
+            &authority.pubkey(),
+            2,
+            4,
+            4,
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer, &authority],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
 
     let account = context
         .banks_client
@@ -140,41 +3110,40 @@ async fn initialize_success() {
         .await
         .unwrap()
         .unwrap();
-
-    let account_data =
-        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
-            .unwrap();
     assert_eq!(
-        account_data.authority.as_slice(),
-        authority.pubkey().as_array()
+        &account.data[RecordData::WRITABLE_START_INDEX..],
+        &[222, 222, 222, 222, 3, 4, 5, 6]
     );
-    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
-    assert_eq!(&account.data[RecordData::WRITABLE_START_INDEX..], data);
 }
 
 #[tokio::test]
-async fn write_success() {
+async fn copy_range_overlapping_success() {
     let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
     let mut context: ProgramTestContext = program_test.start_with_context().await;
 
     let authority = Keypair::new();
     let account = Keypair::new();
-    let data = &[222u8; 8];
-    initialize_storage_account(&mut context, &authority, &account, data).await;
+    initialize_storage_account(
+        &mut context,
+        &authority,
+        &account,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    )
+    .await;
 
-    let new_data = &[200u8; 8];
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction_write(
+        &[instruction_copy_range(
+            &account.pubkey(),
             &account.pubkey(),
             &authority.pubkey(),
             0,
-            new_data,
+            2,
+            6,
         )],
         Some(&context.payer.pubkey()),
         &[&context.payer, &authority],
         context.last_blockhash,
     );
-
     context
         .banks_client
         .process_transaction(transaction)
@@ -187,39 +3156,35 @@ async fn write_success() {
         .await
         .unwrap()
         .unwrap();
-
-    let account_data =
-        bytemuck::try_from_bytes::<RecordData>(&account.data[..RecordData::WRITABLE_START_INDEX])
-            .unwrap();
     assert_eq!(
-        account_data.authority.as_slice(),
-        authority.pubkey().as_array()
+        &account.data[RecordData::WRITABLE_START_INDEX..],
+        &[1, 2, 1, 2, 3, 4, 5, 6]
     );
-    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
-    assert_eq!(&account.data[RecordData::WRITABLE_START_INDEX..], new_data);
 }
 
 #[tokio::test]
-async fn write_fail_wrong_authority() {
+async fn copy_range_fail_wrong_authority() {
     let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
     let mut context: ProgramTestContext = program_test.start_with_context().await;
 
+    let source_authority = Keypair::new();
+    let source = Keypair::new();
+    initialize_storage_account(&mut context, &source_authority, &source, &[1u8; 8]).await;
     let authority = Keypair::new();
     let account = Keypair::new();
-    let data = &[222u8; 8];
-    initialize_storage_account(&mut context, &authority, &account, data).await;
+    initialize_storage_account(&mut context, &authority, &account, &[222u8; 8]).await;
 
-    let new_data = &[200u8; 8];
-    let wrong_authority = Keypair::new();
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction_write(
+        &[instruction_copy_range(
+            &source.pubkey(),
             &account.pubkey(),
-            &wrong_authority.pubkey(),
+            &source_authority.pubkey(),
             0,
-            new_data,
+            0,
+            8,
         )],
         Some(&context.payer.pubkey()),
-        &[&context.payer, &wrong_authority],
+        &[&context.payer, &source_authority],
         context.last_blockhash,
     );
     assert_eq!(
@@ -236,25 +3201,29 @@ async fn write_fail_wrong_authority() {
     );
 }
 
-#[tokio::test]
-async fn close_account_success() {
+/// Runs `instruction` built for a record holding `1..=8` and signed by its authority,
+/// returning the payload and header afterwards.
+async fn shift(
+    instruction: impl FnOnce(&Pubkey, &Pubkey, &Pubkey) -> Instruction,
+) -> Result<(Vec<u8>, RecordData), BanksClientError> {
     let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
     let mut context: ProgramTestContext = program_test.start_with_context().await;
 
     let authority = Keypair::new();
     let account = Keypair::new();
-    let data = &[222u8; 8];
-    let account_length = std::mem::size_of::<RecordData>()
-        .checked_add(data.len())
-        .unwrap();
-    initialize_storage_account(&mut context, &authority, &account, data).await;
-    let recipient = Pubkey::new_unique();
+    initialize_storage_account(
+        &mut context,
+        &authority,
+        &account,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    )
+    .await;
 
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction_close_account(
+        &[instruction(
             &account.pubkey(),
             &authority.pubkey(),
-            &recipient,
+            &context.payer.pubkey(),
         )],
         Some(&context.payer.pubkey()),
         &[&context.payer, &authority],
@@ -263,172 +3232,178 @@ async fn close_account_success() {
     context
         .banks_client
         .process_transaction(transaction)
-        .await
-        .unwrap();
+        .await?;
 
+    let account_data = get_record_header(&mut context, &account.pubkey()).await;
     let account = context
         .banks_client
-        .get_account(recipient)
+        .get_account(account.pubkey())
         .await
         .unwrap()
         .unwrap();
+    Ok((
+        account.data[RecordData::WRITABLE_START_INDEX..].to_vec(),
+        account_data,
+    ))
+}
+
+#[tokio::test]
+async fn shift_insert_with_payer_success() {
+    let (payload, account_data) = shift(|record, authority, payer| {
+        instruction_shift_with_payer(record, authority, payer, 4, 4, 2)
+    })
+    .await
+    .unwrap();
+    assert_eq!(payload, [1, 2, 3, 4, 5, 6, 5, 6, 7, 8]);
+    assert_eq!(account_data.data_len(), 10);
+}
+
+#[tokio::test]
+async fn shift_delete_success() {
+    let (payload, account_data) =
+        shift(|record, authority, _| instruction_shift(record, authority, 6, 2, -2))
+            .await
+            .unwrap();
+    assert_eq!(payload, [1, 2, 3, 4, 7, 8, 7, 8]);
+    assert_eq!(account_data.data_len(), 6);
+}
+
+#[tokio::test]
+async fn shift_fail_before_payload() {
     assert_eq!(
-        account.lamports,
-        1.max(Rent::default().minimum_balance(account_length))
+        shift(|record, authority, _| instruction_shift(record, authority, 1, 2, -2))
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InvalidArgument)
     );
 }
 
-#[tokio::test]
-async fn set_authority_success() {
+/// Verifies the hash of the last four bytes of a record holding `1..=8`.
+async fn verify_hash(algorithm: HashAlgorithm, expected: [u8; 32]) -> Result<(), BanksClientError> {
     let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
     let mut context: ProgramTestContext = program_test.start_with_context().await;
 
     let authority = Keypair::new();
     let account = Keypair::new();
-    let data = &[222u8; 8];
-    initialize_storage_account(&mut context, &authority, &account, data).await;
-    let new_authority = Keypair::new();
+    initialize_storage_account(
+        &mut context,
+        &authority,
+        &account,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    )
+    .await;
 
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction_set_authority(
+        &[instruction_verify_hash(
             &account.pubkey(),
-            &authority.pubkey(),
-            &new_authority.pubkey(),
+            4,
+            4,
+            algorithm,
+            expected,
         )],
         Some(&context.payer.pubkey()),
-        &[&context.payer, &authority],
+        &[&context.payer],
         context.last_blockhash,
     );
-    context
-        .banks_client
-        .process_transaction(transaction)
-        .await
-        .unwrap();
+    context.banks_client.process_transaction(transaction).await
+}
 
-    let account_handle = context
-        .banks_client
-        .get_account(account.pubkey())
-        .await
-        .unwrap()
-        .unwrap();
-    let account_data = bytemuck::try_from_bytes::<RecordData>(
-        &account_handle.data[..RecordData::WRITABLE_START_INDEX],
+#[tokio::test]
+async fn verify_hash_success() {
+    verify_hash(
+        HashAlgorithm::Sha256,
+        solana_program::hash::hash(&[5, 6, 7, 8]).to_bytes(),
     )
+    .await
     .unwrap();
-    assert_eq!(
-        account_data.authority.as_slice(),
-        new_authority.pubkey().as_array()
-    );
-
-    let new_data = &[200u8; 8];
-    let transaction = Transaction::new_signed_with_payer(
-        &[instruction_write(
-            &account.pubkey(),
-            &new_authority.pubkey(),
-            0,
-            new_data,
-        )],
-        Some(&context.payer.pubkey()),
-        &[&context.payer, &new_authority],
-        context.last_blockhash,
-    );
-    context
-        .banks_client
-        .process_transaction(transaction)
-        .await
-        .unwrap();
-
-    let account_handle = context
-        .banks_client
-        .get_account(account.pubkey())
-        .await
-        .unwrap()
-        .unwrap();
-    let account_data = bytemuck::try_from_bytes::<RecordData>(
-        &account_handle.data[..RecordData::WRITABLE_START_INDEX],
+    verify_hash(
+        HashAlgorithm::Keccak256,
+        solana_program::keccak::hash(&[5, 6, 7, 8]).to_bytes(),
     )
+    .await
     .unwrap();
+}
+
+#[tokio::test]
+async fn verify_hash_fail_mismatch() {
     assert_eq!(
-        account_data.authority.as_slice(),
-        new_authority.pubkey().as_array()
-    );
-    assert_eq!(account_data.version, RecordData::CURRENT_VERSION);
-    assert_eq!(
-        &account_handle.data[RecordData::WRITABLE_START_INDEX..],
-        new_data,
+        verify_hash(
+            HashAlgorithm::Sha256,
+            solana_program::hash::hash(&[1, 2, 3, 4]).to_bytes(),
+        )
+        .await
+        .unwrap_err()
+        .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(RecordError::HashMismatch as u32)
+        )
     );
 }
 
 #[tokio::test]
-async fn reallocate_success() {
+async fn read_success() {
     let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
     let mut context: ProgramTestContext = program_test.start_with_context().await;
 
     let authority = Keypair::new();
     let account = Keypair::new();
-    let data = &[222u8; 8];
-    initialize_storage_account(&mut context, &authority, &account, data).await;
-
-    let new_data_length = 16u64;
-    let expected_account_data_length = RecordData::WRITABLE_START_INDEX
-        .checked_add(new_data_length as usize)
-        .unwrap();
-
-    let delta_account_data_length = new_data_length.saturating_sub(data.len() as u64);
-    let additional_lamports_needed =
-        Rent::default().minimum_balance(delta_account_data_length as usize);
+    initialize_storage_account(
+        &mut context,
+        &authority,
+        &account,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    )
+    .await;
 
     let transaction = Transaction::new_signed_with_payer(
-        &[
-            instruction_reallocate(&account.pubkey(), &authority.pubkey(), new_data_length),
-            system_instruction::transfer(
-                &context.payer.pubkey(),
-                &account.pubkey(),
-                additional_lamports_needed,
-            ),
-        ],
+        &[instruction_read(&account.pubkey(), 2, 4)],
         Some(&context.payer.pubkey()),
-        &[&context.payer, &authority],
+        &[&context.payer],
         context.last_blockhash,
     );
-    context
-        .banks_client
-        .process_transaction(transaction)
-        .await
-        .unwrap();
-
-    let account_handle = context
+    let return_data = context
         .banks_client
-        .get_account(account.pubkey())
+        .simulate_transaction(transaction)
         .await
         .unwrap()
+        .simulation_details
+        .unwrap()
+        .return_data
         .unwrap();
+    assert_eq!(return_data.program_id, CUSTOM_PROGRAM_ID);
+    assert_eq!(return_data.data, vec![3, 4, 5, 6]);
+}
 
-    assert_eq!(account_handle.data.len(), expected_account_data_length);
+#[tokio::test]
+async fn read_fail_out_of_bounds() {
+    let program_test = ProgramTest::new("pinocchio_sample", CUSTOM_PROGRAM_ID, None);
+    let mut context: ProgramTestContext = program_test.start_with_context().await;
+
+    let authority = Keypair::new();
+    let account = Keypair::new();
+    initialize_storage_account(
+        &mut context,
+        &authority,
+        &account,
+        &[1, 2, 3, 4, 5, 6, 7, 8],
+    )
+    .await;
 
-    let old_data_length = 8u64;
     let transaction = Transaction::new_signed_with_payer(
-        &[instruction_reallocate(
-            &account.pubkey(),
-            &authority.pubkey(),
-            old_data_length,
-        )],
+        &[instruction_read(&account.pubkey(), 6, 4)],
         Some(&context.payer.pubkey()),
-        &[&context.payer, &authority],
+        &[&context.payer],
         context.last_blockhash,
     );
-    context
-        .banks_client
-        .process_transaction(transaction)
-        .await
-        .unwrap();
-
-    let account = context
-        .banks_client
-        .get_account(account.pubkey())
-        .await
-        .unwrap()
-        .unwrap();
-
-    assert_eq!(account.data.len(), expected_account_data_length);
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::AccountDataTooSmall)
+    );
 }
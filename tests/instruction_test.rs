@@ -0,0 +1,206 @@
+use num_traits::FromPrimitive;
+use pinocchio_sample::{
+    instruction::{HashAlgorithm, RecordInstruction},
+    pinocchio::program_error::ProgramError,
+};
+use proptest::{collection::vec, prelude::*};
+
+fn instruction() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        Just(RecordInstruction::Initialize.pack()),
+        (any::<u64>(), vec(any::<u8>(), 0..256)).prop_map(|(offset, data)| {
+            RecordInstruction::Write {
+                offset,
+                data: &data,
+            }
+            .pack()
+        }),
+        Just(RecordInstruction::SetAuthority.pack()),
+        Just(RecordInstruction::CloseAccount.pack()),
+        any::<u64>().prop_map(|data_length| RecordInstruction::Reallocate { data_length }.pack()),
+        Just(RecordInstruction::InitializeChecked.pack()),
+        vec(any::<u8>(), 0..256)
+            .prop_map(|data| RecordInstruction::InitializeWithData { data: &data }.pack()),
+        (any::<u64>(), vec(any::<u8>(), 0..32)).prop_map(|(data_length, seed)| {
+            RecordInstruction::CreateRecord {
+                data_length,
+                seed: &seed,
+            }
+            .pack()
+        }),
+        (any::<u64>(), vec(any::<u8>(), 0..32)).prop_map(|(data_length, seed)| {
+            RecordInstruction::CreateRecordPda {
+                data_length,
+                seed: &seed,
+            }
+            .pack()
+        }),
+        Just(RecordInstruction::ProposeAuthority.pack()),
+        Just(RecordInstruction::AcceptAuthority.pack()),
+        Just(RecordInstruction::CancelProposal.pack()),
+        Just(RecordInstruction::Freeze.pack()),
+        any::<bool>().prop_map(|with_payer| RecordInstruction::Migrate { with_payer }.pack()),
+        any::<u8>().prop_map(|m| RecordInstruction::InitializeMultisig { m }.pack()),
+        (any::<u64>(), any::<u64>(), any::<u64>()).prop_map(|(start, end, expiry_slot)| {
+            RecordInstruction::ApproveDelegate {
+                start,
+                end,
+                expiry_slot,
+            }
+            .pack()
+        }),
+        Just(RecordInstruction::RevokeDelegate.pack()),
+        (vec(any::<u8>(), 0..256), any::<bool>()).prop_map(|(data, with_payer)| {
+            RecordInstruction::Append {
+                data: &data,
+                with_payer,
+            }
+            .pack()
+        }),
+        (any::<u64>(), any::<u64>(), vec(any::<u8>(), 0..256)).prop_map(
+            |(expected_seq, offset, data)| {
+                RecordInstruction::WriteIfVersion {
+                    expected_seq,
+                    offset,
+                    data: &data,
+                }
+                .pack()
+            }
+        ),
+        vec((any::<u32>(), vec(any::<u8>(), 0..64)), 1..8).prop_map(|segments| {
+            RecordInstruction::WriteMany {
+                segments: segments
+                    .iter()
+                    .map(|(offset, data)| (*offset, data.as_slice()))
+                    .collect(),
+            }
+            .pack()
+        }),
+        (any::<u64>(), any::<u64>(), any::<u8>()).prop_map(|(offset, length, byte)| {
+            RecordInstruction::Fill {
+                offset,
+                length,
+                byte,
+            }
+            .pack()
+        }),
+        (any::<u64>(), any::<u64>(), any::<u64>()).prop_map(|(src_offset, dst_offset, length)| {
+            RecordInstruction::CopyRange {
+                src_offset,
+                dst_offset,
+                length,
+            }
+            .pack()
+        }),
+        (any::<u64>(), any::<u64>(), any::<i64>(), any::<bool>()).prop_map(
+            |(offset, length, delta, with_payer)| {
+                RecordInstruction::Shift {
+                    offset,
+                    length,
+                    delta,
+                    with_payer,
+                }
+                .pack()
+            }
+        ),
+        (
+            any::<u64>(),
+            any::<u64>(),
+            any::<u8>().prop_filter_map("unknown hash algorithm", HashAlgorithm::from_u8),
+            any::<[u8; 32]>(),
+        )
+            .prop_map(|(offset, length, algorithm, expected)| {
+                RecordInstruction::VerifyHash {
+                    offset,
+                    length,
+                    algorithm,
+                    expected,
+                }
+                .pack()
+            }),
+        (any::<u64>(), any::<u64>()).prop_map(|(offset, length)| RecordInstruction::Read {
+            offset,
+            length
+        }
+        .pack()),
+        any::<u64>().prop_map(|data_length| {
+            RecordInstruction::ReallocateWithPayer { data_length }.pack()
+        }),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn pack_unpack_round_trip(packed in instruction()) {
+        let instruction = RecordInstruction::unpack(&packed).unwrap();
+        prop_assert_eq!(instruction.pack(), packed);
+    }
+
+    #[test]
+    fn unpack_rejects_truncated(packed in instruction(), cut in any::<prop::sample::Index>()) {
+        let truncated = &packed[..cut.index(packed.len())];
+        prop_assert_eq!(
+            RecordInstruction::unpack(truncated),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn unpack_rejects_trailing_bytes(packed in instruction(), extra in vec(any::<u8>(), 1..16)) {
+        let mut input = packed;
+        input.extend_from_slice(&extra);
+        prop_assert_eq!(
+            RecordInstruction::unpack(&input),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn unpack_arbitrary_input(input in vec(any::<u8>(), 0..64)) {
+        if let Ok(instruction) = RecordInstruction::unpack(&input) {
+            prop_assert_eq!(instruction.pack(), input);
+        }
+    }
+}
+
+#[test]
+fn unpack_rejects_oversized_length() {
+    let mut input = RecordInstruction::Write {
+        offset: 0,
+        data: &[1, 2, 3],
+    }
+    .pack();
+    input[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(
+        RecordInstruction::unpack(&input),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn unpack_rejects_invalid_flag() {
+    let mut input = RecordInstruction::Migrate { with_payer: true }.pack();
+    input[1] = 2;
+    assert_eq!(
+        RecordInstruction::unpack(&input),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+#[should_panic]
+fn pack_panics_on_too_many_segments() {
+    RecordInstruction::WriteMany {
+        segments: vec![(0, &[][..]); 256],
+    }
+    .pack();
+}
+
+#[test]
+#[should_panic]
+fn pack_panics_on_oversized_segment() {
+    RecordInstruction::WriteMany {
+        segments: vec![(0, &[0; 65536][..])],
+    }
+    .pack();
+}